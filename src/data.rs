@@ -1,30 +1,47 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use age::secrecy::Secret;
-use age::{Decryptor, Encryptor};
-use anyhow::bail;
+use age::{x25519, Decryptor, Encryptor};
+use anyhow::{anyhow, bail};
 use directories::BaseDirs;
 use druid::im::Vector;
 use druid::{Data, Lens};
-use keyring::Entry;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zeroize::Zeroizing;
 
-static CONFIG_PATH: Lazy<PathBuf> = Lazy::new(|| {
+use crate::format::Format;
+use crate::generator::GeneratorSettings;
+use crate::store::SecretStoreKind;
+use crate::util::SecretString;
+
+static CONFIG_PATH: Lazy<Option<PathBuf>> = Lazy::new(|| {
     let mut pargs = pico_args::Arguments::from_env();
-    match pargs
+    pargs
         .opt_value_from_str("--config-path")
         .expect("Failed to parse command line arguments")
-    {
-        Some(config_dir) => config_dir,
+});
+
+/// Where the settings are stored in `format`. A path given with `--config-path` is used as is, otherwise the extension
+/// follows the format.
+fn config_path(format: Format) -> PathBuf {
+    match &*CONFIG_PATH {
+        Some(path) => path.clone(),
         None => BaseDirs::new()
             .expect("Could find the settings path")
             .preference_dir()
-            .join("lol_account_manager.yml")
+            .join("lol_account_manager")
+            .with_extension(format.extension())
     }
-});
+}
 
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Data, Serialize, Deserialize)]
 pub enum Theme {
@@ -33,13 +50,25 @@ pub enum Theme {
     Dark
 }
 
+const MAX_RECENT_DATABASES: usize = 10;
+
 #[derive(Debug, Clone, PartialEq, Data, Lens, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Settings {
     pub close_on_login: bool,
     pub force_focus: bool,
     pub theme: Theme,
-    pub last_database: Option<String>
+    #[serde(rename = "last_database", skip_serializing)]
+    legacy_database: Option<String>,
+    pub lock_on_minimize: bool,
+    pub lock_timeout: u32,
+    pub secret_store: SecretStoreKind,
+    pub trash_retention: u32,
+    pub databases: Vector<DatabaseSettings>,
+    pub generator: GeneratorSettings,
+    /// The format the settings file was written in.
+    #[serde(skip)]
+    format: Format
 }
 
 impl Default for Settings {
@@ -48,16 +77,39 @@ impl Default for Settings {
             close_on_login: true,
             force_focus: true,
             theme: Theme::Light,
-            last_database: None
+            legacy_database: None,
+            lock_on_minimize: false,
+            lock_timeout: 0,
+            secret_store: SecretStoreKind::Keyring,
+            trash_retention: 30,
+            databases: Vector::new(),
+            generator: GeneratorSettings::default(),
+            format: Format::preferred().unwrap_or_default()
         }
     }
 }
 
 impl Settings {
     pub fn load() -> anyhow::Result<Self> {
-        Ok(match CONFIG_PATH.exists() {
-            true => serde_yaml::from_reader(File::open(&*CONFIG_PATH)?)?,
-            false => {
+        let existing = Format::preferred()
+            .into_iter()
+            .chain(Format::ALL)
+            .map(config_path)
+            .find(|path| path.exists());
+        Ok(match existing {
+            Some(path) => {
+                let content = std::fs::read(path)?;
+                let format = Format::detect(&content);
+                let mut settings: Self = format.read(&content)?;
+                settings.format = format;
+                if let Some(path) = settings.legacy_database.take() {
+                    if settings.database(&path).is_none() {
+                        settings.databases.push_front(DatabaseSettings::new(&path));
+                    }
+                }
+                settings
+            }
+            None => {
                 let result = Self::default();
                 Self::save(&result)?;
                 result
@@ -66,97 +118,700 @@ impl Settings {
     }
 
     pub fn save(&self) -> anyhow::Result<()> {
-        if let Some(path) = CONFIG_PATH.parent() {
-            std::fs::create_dir_all(path)?;
+        let format = Format::preferred().unwrap_or(self.format);
+        let path = config_path(format);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(&path)?);
+        format.write(&mut writer, self)?;
+        writer.flush()?;
+        // A file in the previous format would otherwise be found again on the next start.
+        for old in Format::ALL.map(config_path) {
+            if old != path && old.exists() {
+                std::fs::remove_file(old)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn last_database(&self) -> Option<&DatabaseSettings> {
+        self.databases.front()
+    }
+
+    pub fn database(&self, path: &str) -> Option<&DatabaseSettings> {
+        self.databases.iter().find(|db| db.path == path)
+    }
+
+    /// Moves the database to the front of the recently used list and forgets the databases that fall off the end.
+    pub fn open_database(&mut self, path: &str) -> anyhow::Result<()> {
+        let index = self.databases.iter().position(|db| db.path == path);
+        let entry = match index {
+            Some(index) => self.databases.remove(index),
+            None => DatabaseSettings::new(path)
+        };
+        self.databases.push_front(entry);
+        let store = self.secret_store.get();
+        while self.databases.len() > MAX_RECENT_DATABASES {
+            if let Some(evicted) = self.databases.pop_back() {
+                store.delete(&evicted.path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remember_password(&self, path: &str) -> bool {
+        self.database(path).map_or(true, |db| db.remember_password)
+    }
+
+    pub fn password_store(&self, path: &str) -> Option<SecretStoreKind> {
+        match self.remember_password(path) && self.secret_store != SecretStoreKind::Disabled {
+            true => Some(self.secret_store),
+            false => None
+        }
+    }
+
+    /// Moves every saved password from the store used by `old` to the store used by these settings. The password of
+    /// `primary` is already known, so it is stored even if it was never saved before.
+    pub fn move_passwords(&self, old: &Settings, primary: &str, password: &SecretString) -> anyhow::Result<()> {
+        let paths: HashSet<&str> = self
+            .databases
+            .iter()
+            .chain(old.databases.iter())
+            .map(|db| db.path.as_str())
+            .chain([primary])
+            .collect();
+        for path in paths {
+            let (from, to) = (old.password_store(path), self.password_store(path));
+            if from == to {
+                continue;
+            }
+            let secret = match path == primary {
+                true => Some(password.clone()),
+                false => from.map_or(Ok(None), |store| store.get().get(path))?
+            };
+            if let Some(from) = from {
+                from.get().delete(path)?;
+            }
+            if let (Some(to), Some(secret)) = (to, secret) {
+                to.get().store(path, &secret)?;
+            }
         }
-        serde_yaml::to_writer(File::create(&*CONFIG_PATH)?, self)?;
         Ok(())
     }
+
+    pub fn saved_passwords(&self) -> Vector<String> {
+        let store = self.secret_store.get();
+        self.databases
+            .iter()
+            .filter(|db| matches!(store.get(&db.path), Ok(Some(_))))
+            .map(|db| db.path.clone())
+            .collect()
+    }
+
+    pub fn forget_database(&mut self, path: &str) -> anyhow::Result<()> {
+        self.databases.retain(|db| db.path != path);
+        self.secret_store.get().delete(path)
+    }
+
+    pub fn set_remember_password(&mut self, path: &str, remember: bool) {
+        match self.databases.iter_mut().find(|db| db.path == path) {
+            Some(db) => db.remember_password = remember,
+            None => self.databases.push_back(DatabaseSettings {
+                remember_password: remember,
+                ..DatabaseSettings::new(path)
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Data, Lens, Serialize, Deserialize)]
+pub struct DatabaseSettings {
+    pub path: String,
+    #[serde(default)]
+    pub label: String,
+    pub remember_password: bool
+}
+
+impl DatabaseSettings {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            label: String::new(),
+            remember_password: true
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        match self.label.is_empty() {
+            true => database_name(&self.path),
+            false => self.label.clone()
+        }
+    }
+}
+
+fn database_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .map(|name| {
+            let name = name.trim_end_matches(".age");
+            Format::ALL
+                .map(Format::extension)
+                .into_iter()
+                .chain(["yaml"])
+                .find_map(|ext| name.strip_suffix(ext)?.strip_suffix('.'))
+                .unwrap_or(name)
+                .to_string()
+        })
+        .unwrap_or_else(|| path.to_owned())
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AccountId(Uuid);
+
+impl AccountId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    fn nil() -> Self {
+        Self(Uuid::nil())
+    }
+
+    // Entries written by older versions have no id. Deriving it from the content gives every machine the same id
+    // for the same entry, so that the copies of a synced database can still be merged.
+    fn legacy(account: &Account) -> Self {
+        let name = format!("{}\n{}", account.name, account.username);
+        Self(Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()))
+    }
+}
+
+impl Default for AccountId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Data for AccountId {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Data, Lens, Serialize, Deserialize)]
 pub struct Account {
+    #[serde(default = "AccountId::nil")]
+    pub id: AccountId,
     pub name: String,
     pub username: String,
-    pub password: String,
+    pub password: SecretString,
     #[serde(with = "crate::util::string_list")]
-    pub notes: String
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Vector::is_empty")]
+    pub tags: Vector<String>,
+    #[serde(default, skip_serializing_if = "Vector::is_empty")]
+    pub fields: Vector<CustomField>,
+    #[serde(default, skip_serializing_if = "Vector::is_empty")]
+    pub history: Vector<Change>
+}
+
+impl Account {
+    /// Adds an entry to the history for every field that differs from `old`.
+    pub fn record_changes(&mut self, old: &Account) {
+        let time = unix_time();
+        let changes = [
+            (Field::Name, old.name != self.name, old.name.as_str()),
+            (Field::Username, old.username != self.username, old.username.as_str()),
+            (Field::Password, old.password != self.password, old.password.expose()),
+            (Field::Notes, old.notes != self.notes, old.notes.as_str())
+        ];
+        for (field, changed, value) in changes {
+            if changed {
+                self.history.push_back(Change {
+                    time,
+                    field,
+                    old: value.into()
+                });
+            }
+        }
+    }
+
+    pub fn tags_text(&self) -> String {
+        self.tags.iter().cloned().collect::<Vec<_>>().join(", ")
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        if !self.tags.iter().any(|t| t == tag) {
+            self.tags.push_back(tag.to_owned());
+        }
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        self.tags.retain(|t| t != tag);
+    }
+
+    /// Cleans up tags that were typed in by hand: surrounding whitespace, empty entries and duplicates are removed.
+    pub fn normalize_tags(&mut self) {
+        let tags = std::mem::take(&mut self.tags);
+        for tag in tags.iter() {
+            let tag = tag.trim();
+            if !tag.is_empty() {
+                self.add_tag(tag);
+            }
+        }
+    }
+
+    pub fn restore(&mut self, change: &Change) {
+        match change.field {
+            Field::Name => self.name = change.old.expose().to_owned(),
+            Field::Username => self.username = change.old.expose().to_owned(),
+            Field::Password => self.password = change.old.clone(),
+            Field::Notes => self.notes = change.old.expose().to_owned()
+        }
+    }
+}
+
+/// Additional information that does not fit into any of the regular fields, like the region or the email of an account.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Data, Lens, Serialize, Deserialize)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Data, Serialize, Deserialize)]
+pub enum Field {
+    Name,
+    Username,
+    Password,
+    Notes
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Field::Name => f.write_str("Name"),
+            Field::Username => f.write_str("Username"),
+            Field::Password => f.write_str("Password"),
+            Field::Notes => f.write_str("Notes")
+        }
+    }
+}
+
+/// A previous value of a field. Old values are stored as secrets because most of them are passwords.
+#[derive(Debug, Clone, PartialEq, Data, Lens, Serialize, Deserialize)]
+pub struct Change {
+    /// Seconds since the unix epoch
+    pub time: u64,
+    pub field: Field,
+    pub old: SecretString
+}
+
+impl Change {
+    pub fn days_ago(&self) -> u64 {
+        days_since(self.time)
+    }
+}
+
+/// Identifies the state of a database file on disk, so that changes made by other programs can be noticed.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct FileVersion {
+    modified: Option<SystemTime>,
+    hash: u64
+}
+
+impl FileVersion {
+    fn new(metadata: &std::fs::Metadata, content: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        Self {
+            modified: metadata.modified().ok(),
+            hash: hasher.finish()
+        }
+    }
+
+    fn read(path: &str) -> std::io::Result<Self> {
+        let content = std::fs::read(path)?;
+        Ok(Self::new(&std::fs::metadata(path)?, &content))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Data, Lens, Serialize, Deserialize)]
+pub struct TrashedAccount {
+    pub account: Account,
+    /// Seconds since the unix epoch
+    pub deleted: u64
+}
+
+impl TrashedAccount {
+    pub fn days_in_trash(&self) -> u64 {
+        days_since(self.deleted)
+    }
+}
+
+/// The content of a database file.
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    accounts: Vector<Account>,
+    #[serde(default)]
+    trash: Vector<TrashedAccount>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredPayload {
+    Current(Payload),
+    // Older versions stored only the list of accounts
+    Legacy(Vector<Account>)
+}
+
+impl From<StoredPayload> for Payload {
+    fn from(value: StoredPayload) -> Self {
+        let mut payload = match value {
+            StoredPayload::Current(payload) => payload,
+            StoredPayload::Legacy(accounts) => Payload {
+                accounts,
+                trash: Vector::new()
+            }
+        };
+        payload.accounts = assign_ids(payload.accounts);
+        payload
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+fn days_since(time: u64) -> u64 {
+    unix_time().saturating_sub(time) / (24 * 60 * 60)
 }
 
 #[derive(Debug, Clone, Data, Lens, PartialEq)]
 pub struct Database {
     pub accounts: Vector<Account>,
-    pub password: String,
-    pub path: String
+    pub trash: Vector<TrashedAccount>,
+    pub password: SecretString,
+    pub path: String,
+    #[data(eq)]
+    pub version: FileVersion,
+    /// The format of the encrypted content. It is kept when the database is saved unless `--format` asks for another one.
+    pub format: Format
 }
 
 impl Database {
-    pub fn new(path: &str, password: &str) -> anyhow::Result<Self> {
-        let db = Self {
-            accounts: Default::default(),
-            password: password.to_owned(),
-            path: path.to_owned()
+    pub fn new(path: &str, password: &SecretString) -> anyhow::Result<Self> {
+        Self::create(path, password, Vector::new())
+    }
+
+    /// Creates a new database that already contains `accounts`.
+    pub fn create(path: &str, password: &SecretString, accounts: Vector<Account>) -> anyhow::Result<Self> {
+        let mut db = Self {
+            accounts,
+            trash: Default::default(),
+            password: password.clone(),
+            path: path.to_owned(),
+            version: FileVersion::default(),
+            format: Format::preferred().unwrap_or_default()
         };
         db.save()?;
         Ok(db)
     }
 
-    pub fn import(input: &str, output: &str, password: &str) -> anyhow::Result<Self> {
-        let accounts: Vector<Account> = serde_yaml::from_reader(File::open(input)?)?;
-        let db = Self {
+    pub fn import(input: &str, output: &str, password: &SecretString) -> anyhow::Result<Self> {
+        let (Payload { accounts, trash }, _) = parse_payload(&std::fs::read(input)?)?;
+        let mut db = Self {
             accounts,
-            password: password.to_owned(),
-            path: output.to_owned()
+            trash,
+            password: password.clone(),
+            path: output.to_owned(),
+            version: FileVersion::default(),
+            format: Format::preferred().unwrap_or_default()
         };
         db.save()?;
         Ok(db)
     }
 
-    pub fn load(path: &str, password: &str) -> anyhow::Result<Self> {
+    pub fn load(path: &str, password: &SecretString) -> anyhow::Result<Self> {
         let time = Instant::now();
-        let file = File::open(path)?;
-        let decryptor = match Decryptor::new(file)? {
+        let content = std::fs::read(path)?;
+        let version = FileVersion::new(&std::fs::metadata(path)?, &content);
+        let decryptor = match Decryptor::new(content.as_slice())? {
             Decryptor::Passphrase(d) => d,
             _ => bail!("Only password encrypted files are supported!")
         };
-        let reader = decryptor.decrypt(&Secret::new(password.to_owned()), None)?;
-        let accounts: Vector<Account> = serde_yaml::from_reader(reader)?;
+        let reader = decryptor.decrypt(&Secret::new(password.expose().to_owned()), None)?;
+        let (Payload { accounts, trash }, format) = parse_payload(&read_all(reader)?)?;
         println!("loading time: {}ms", time.elapsed().as_secs_f64() * 1000.0);
         Ok(Self {
             accounts,
-            password: password.to_owned(),
-            path: path.to_owned()
+            trash,
+            password: password.clone(),
+            path: path.to_owned(),
+            version,
+            format
         })
     }
 
-    pub fn save(&self) -> anyhow::Result<()> {
+    pub fn name(&self) -> String {
+        database_name(&self.path)
+    }
+
+    pub fn position(&self, id: AccountId) -> Option<usize> {
+        self.accounts.iter().position(|acc| acc.id == id)
+    }
+
+    pub fn account(&self, id: AccountId) -> Option<&Account> {
+        self.accounts.iter().find(|acc| acc.id == id)
+    }
+
+    pub fn move_to_trash(&mut self, index: usize) {
+        let account = self.accounts.remove(index);
+        self.trash.push_back(TrashedAccount {
+            account,
+            deleted: unix_time()
+        });
+    }
+
+    pub fn restore(&mut self, id: AccountId) {
+        if let Some(index) = self.trash.iter().position(|entry| entry.account.id == id) {
+            let entry = self.trash.remove(index);
+            self.accounts.push_back(entry.account);
+        }
+    }
+
+    pub fn purge(&mut self, id: AccountId) {
+        self.trash.retain(|entry| entry.account.id != id);
+    }
+
+    /// Removes the accounts that were in the trash for longer than `days`. Zero keeps them until the trash is emptied.
+    pub fn purge_expired(&mut self, days: u32) {
+        if days > 0 {
+            let cutoff = unix_time().saturating_sub(days as u64 * 24 * 60 * 60);
+            self.trash.retain(|entry| entry.deleted >= cutoff);
+        }
+    }
+
+    /// Checks whether the file was modified by someone else since it was loaded or saved by us.
+    pub fn changed_on_disk(&self) -> anyhow::Result<bool> {
+        let modified = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata.modified().ok(),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err.into())
+        };
+        if modified.is_some() && modified == self.version.modified {
+            return Ok(false);
+        }
+        Ok(FileVersion::read(&self.path)?.hash != self.version.hash)
+    }
+
+    pub fn save(&mut self) -> anyhow::Result<()> {
         let path = Path::new(&self.path);
         if let Some(path) = path.parent() {
             std::fs::create_dir_all(path)?;
         }
         let time = Instant::now();
-        let encryptor = Encryptor::with_user_passphrase(Secret::new(self.password.clone()));
-        let file = File::create(path)?;
-        let mut writer = encryptor.wrap_output(file)?;
-        serde_yaml::to_writer(&mut writer, &self.accounts)?;
-        writer.finish()?;
+        let encryptor = Encryptor::with_user_passphrase(Secret::new(self.password.expose().to_owned()));
+        let payload = Payload {
+            accounts: self.accounts.clone(),
+            trash: self.trash.clone()
+        };
+        let format = Format::preferred().unwrap_or(self.format);
+        write_encrypted(File::create(path)?, encryptor, &payload, format)?;
+        self.format = format;
+        self.version = FileVersion::read(&self.path)?;
         println!("writing time: {}ms", time.elapsed().as_secs_f64() * 1000.0);
         Ok(())
     }
 }
 
-pub struct Password;
+fn write_encrypted(output: impl Write, encryptor: Encryptor, payload: &Payload, format: Format) -> anyhow::Result<()> {
+    let mut writer = encryptor.wrap_output(output)?;
+    format.write(&mut writer, payload)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// How a file that is shared with someone else is encrypted.
+#[derive(Debug, Clone, PartialEq, Data)]
+pub enum Protection {
+    Passphrase(SecretString),
+    /// The public keys of the age identities that can open the file, separated by commas or whitespace.
+    Recipients(String)
+}
 
-impl Password {
-    fn entry(path: &str) -> keyring::Result<Entry> {
-        Entry::new(path, "local")
+impl Protection {
+    fn encryptor(&self) -> anyhow::Result<Encryptor> {
+        match self {
+            Protection::Passphrase(passphrase) => Ok(Encryptor::with_user_passphrase(Secret::new(passphrase.expose().to_owned()))),
+            Protection::Recipients(keys) => {
+                let mut recipients: Vec<Box<dyn age::Recipient + Send>> = Vec::new();
+                for key in keys
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|key| !key.is_empty())
+                {
+                    let recipient = key
+                        .parse::<x25519::Recipient>()
+                        .map_err(|err| anyhow!("{} is not a valid recipient: {}", key, err))?;
+                    recipients.push(Box::new(recipient));
+                }
+                Encryptor::with_recipients(recipients).ok_or_else(|| anyhow!("At least one recipient is required"))
+            }
+        }
     }
+}
+
+/// Writes `accounts` into a new file that can be opened or merged by another instance of the program.
+///
+/// A file that is protected by a passphrase is a regular database. The history of the accounts is left out.
+pub fn write_shared(path: &str, accounts: &Vector<Account>, protection: &Protection) -> anyhow::Result<()> {
+    std::fs::write(path, encrypt_shared(accounts, protection)?)?;
+    Ok(())
+}
+
+/// The content of the file that [`write_shared`] would write.
+pub fn encrypt_shared(accounts: &Vector<Account>, protection: &Protection) -> anyhow::Result<Vec<u8>> {
+    // The history holds every previous password, which is nothing the recipients should get.
+    let accounts = accounts
+        .iter()
+        .cloned()
+        .map(|mut account| {
+            account.history.clear();
+            account
+        })
+        .collect();
+    let payload = Payload { accounts, trash: Vector::new() };
+    let mut content = Vec::new();
+    write_encrypted(&mut content, protection.encryptor()?, &payload, Format::preferred().unwrap_or_default())?;
+    Ok(content)
+}
 
-    pub fn store(path: &str, password: &str) -> keyring::Result<()> {
-        Self::entry(path)?.set_password(password)
+/// Reads the accounts of a file that was written by [`write_shared`] or of any other database.
+///
+/// `secret` is either the passphrase or, for files that were encrypted for recipients, the content of an age identity file.
+pub fn read_shared(path: &str, secret: &SecretString) -> anyhow::Result<Vector<Account>> {
+    let reader: Box<dyn Read> = match Decryptor::new(File::open(path)?)? {
+        Decryptor::Passphrase(decryptor) => Box::new(decryptor.decrypt(&Secret::new(secret.expose().to_owned()), None)?),
+        Decryptor::Recipients(decryptor) => {
+            let mut identities = Vec::new();
+            for key in secret
+                .expose()
+                .split_whitespace()
+                .filter(|key| key.starts_with("AGE-SECRET-KEY-"))
+            {
+                // The error messages of age never contain the key itself.
+                identities.push(
+                    key.parse::<x25519::Identity>()
+                        .map_err(|err| anyhow!(err))?
+                );
+            }
+            if identities.is_empty() {
+                bail!("The file is encrypted for specific recipients. Enter the secret key of one of them.");
+            }
+            Box::new(
+                decryptor.decrypt(
+                    identities
+                        .iter()
+                        .map(|identity| identity as &dyn age::Identity)
+                )?
+            )
+        }
+    };
+    let (payload, _) = parse_payload(&read_all(reader)?)?;
+    Ok(payload.accounts)
+}
+
+/// Writes `accounts` unencrypted as a plain list, like the yaml export always did. Toml has no top level lists, so
+/// there the list is stored under `accounts` in the same layout as a database.
+pub fn write_export(path: &Path, accounts: &Vector<Account>, format: Format) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        Format::Toml => format.write(
+            &mut writer,
+            &Payload {
+                accounts: accounts.clone(),
+                trash: Vector::new()
+            }
+        )?,
+        _ => format.write(&mut writer, accounts)?
     }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads the accounts of an unencrypted file, like the ones that are written by the export.
+pub fn read_export(path: &str) -> anyhow::Result<Vector<Account>> {
+    let (payload, _) = parse_payload(&std::fs::read(path)?)?;
+    Ok(payload.accounts)
+}
+
+fn read_all(mut reader: impl Read) -> anyhow::Result<Zeroizing<Vec<u8>>> {
+    let mut content = Zeroizing::new(Vec::new());
+    reader.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn parse_payload(content: &[u8]) -> anyhow::Result<(Payload, Format)> {
+    let format = Format::detect(content);
+    let payload: StoredPayload = format.read(content)?;
+    Ok((payload.into(), format))
+}
+
+fn assign_ids(mut accounts: Vector<Account>) -> Vector<Account> {
+    let mut seen = HashSet::new();
+    for account in accounts.iter_mut() {
+        if account.id == AccountId::nil() {
+            account.id = AccountId::legacy(account);
+        }
+        if !seen.insert(account.id) {
+            account.id = AccountId::new();
+            seen.insert(account.id);
+        }
+    }
+    accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(store: SecretStoreKind) -> Settings {
+        let mut settings = Settings {
+            secret_store: store,
+            ..Settings::default()
+        };
+        settings.set_remember_password("move/primary.age", true);
+        settings.set_remember_password("move/other.age", true);
+        settings.set_remember_password("move/forgotten.age", false);
+        settings
+    }
+
+    #[test]
+    fn database_names_hide_the_extension() {
+        assert_eq!(database_name("/home/me/accounts.yml.age"), "accounts");
+        assert_eq!(database_name("accounts.yaml"), "accounts");
+        assert_eq!(database_name("accounts.json.age"), "accounts");
+        assert_eq!(database_name("accounts.toml"), "accounts");
+        assert_eq!(database_name("accounts.backup.age"), "accounts.backup");
+        assert_eq!(database_name("json"), "json");
+    }
+
+    #[test]
+    fn passwords_move_with_the_store() {
+        let memory = SecretStoreKind::Memory.get();
+        let (old, new) = (settings(SecretStoreKind::Disabled), settings(SecretStoreKind::Memory));
+        new.move_passwords(&old, "move/primary.age", &"primary".into()).unwrap();
+        assert_eq!(memory.get("move/primary.age").unwrap(), Some("primary".into()));
+        assert_eq!(memory.get("move/other.age").unwrap(), None);
+        assert_eq!(memory.get("move/forgotten.age").unwrap(), None);
 
-    pub fn get(path: &str) -> keyring::Result<String> {
-        Self::entry(path)?.get_password()
+        memory.store("move/other.age", &"other".into()).unwrap();
+        old.move_passwords(&new, "move/primary.age", &"primary".into()).unwrap();
+        assert_eq!(memory.get("move/primary.age").unwrap(), None);
+        assert_eq!(memory.get("move/other.age").unwrap(), None);
     }
 }
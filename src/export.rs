@@ -0,0 +1,204 @@
+use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use druid::im::Vector;
+use druid::{Data, FileDialogOptions, FileSpec, Lens};
+
+use crate::data::{self, Account, Field};
+use crate::format::Format;
+
+pub const YAML: FileSpec = FileSpec::new("yaml file", &["yml", "yaml"]);
+pub const TXT: FileSpec = FileSpec::new("text file", &["txt"]);
+pub const CSV: FileSpec = FileSpec::new("csv file", &["csv"]);
+pub const JSON: FileSpec = FileSpec::new("json file", &["json"]);
+pub const TOML: FileSpec = FileSpec::new("toml file", &["toml"]);
+pub const AGE_YAML: FileSpec = FileSpec::new("encrypted yaml file", &["yml.age", "yaml.age"]);
+
+/// Exported files that are still waiting for [`delete_later`].
+static PENDING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// The options for the save dialog that asks where the accounts should be exported to.
+/// The file type that is selected at first follows `--format`.
+pub fn dialog_options() -> FileDialogOptions {
+    let default = match Format::preferred() {
+        Some(Format::Yaml) => YAML,
+        Some(Format::Json) => JSON,
+        Some(Format::Toml) => TOML,
+        None => TXT
+    };
+    FileDialogOptions::new()
+        .allowed_types(vec![YAML, JSON, TOML, TXT, CSV])
+        .default_name("accounts")
+        .default_type(default)
+}
+
+/// The options for the save dialog of an encrypted export.
+pub fn encrypted_dialog_options() -> FileDialogOptions {
+    FileDialogOptions::new()
+        .allowed_types(vec![AGE_YAML])
+        .default_name("accounts.yml.age")
+        .default_type(AGE_YAML)
+}
+
+/// Whether `path` should be written by an encrypted export instead of [`export`].
+pub fn is_encrypted(path: &Path) -> bool {
+    path.to_string_lossy().to_lowercase().ends_with(".age")
+}
+
+/// What ends up in an unencrypted export.
+#[derive(Debug, Clone, Data, Lens)]
+pub struct ExportOptions {
+    pub passwords: bool,
+    pub notes: bool,
+    /// Whether only the selected accounts are exported instead of all accounts of the database.
+    pub selected_only: bool,
+    /// Minutes until the exported file is deleted again. Zero keeps it.
+    pub delete_after: u32
+}
+
+impl ExportOptions {
+    pub fn new(selected_only: bool) -> Self {
+        Self {
+            passwords: true,
+            notes: true,
+            selected_only,
+            delete_after: 0
+        }
+    }
+
+    /// Removes everything from `accounts` that should not be exported. Old passwords and notes are always left out of the history.
+    pub fn apply(&self, accounts: &Vector<Account>) -> Vector<Account> {
+        accounts
+            .iter()
+            .map(|account| {
+                let mut account = account.clone();
+                if !self.passwords {
+                    account.password = Default::default();
+                }
+                if !self.notes {
+                    account.notes.clear();
+                }
+                account
+                    .history
+                    .retain(|change| !matches!(change.field, Field::Password | Field::Notes));
+                account
+            })
+            .collect()
+    }
+}
+
+/// Overwrites and removes `path` after `minutes`, or earlier by [`delete_pending`] when the program is closed before.
+pub fn delete_later(path: PathBuf, minutes: u32) {
+    PENDING.lock().unwrap().push(path.clone());
+    spawn(move || {
+        sleep(Duration::from_secs(minutes as u64 * 60));
+        let mut pending = PENDING.lock().unwrap();
+        // The file is gone already if the program is shutting down.
+        if let Some(index) = pending.iter().position(|pending| *pending == path) {
+            pending.remove(index);
+            delete(&path);
+        }
+    });
+}
+
+/// Deletes every exported file that is still waiting for its timer. Called when the program exits.
+pub fn delete_pending() {
+    for path in PENDING.lock().unwrap().drain(..) {
+        delete(&path);
+    }
+}
+
+fn delete(path: &Path) {
+    if let Err(err) = overwrite_and_remove(path) {
+        eprintln!("Failed to delete {}: {}", path.display(), err);
+    }
+}
+
+/// Overwrites the content of `path` with zeros before removing it. On SSDs and copy-on-write file systems the old
+/// content can survive anyway, so this only makes it less likely to be recovered.
+fn overwrite_and_remove(path: &Path) -> std::io::Result<()> {
+    let length = std::fs::metadata(path)?.len();
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.write_all(&vec![0; length as usize])?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)
+}
+
+/// Writes the accounts unencrypted to `path`. The format is picked based on the file extension.
+pub fn export(path: &Path, accounts: &Vector<Account>) -> anyhow::Result<()> {
+    let spec = path.extension().and_then(OsStr::to_str).and_then(|ext| {
+        [TXT, YAML, JSON, TOML, CSV]
+            .into_iter()
+            .find(|spec| spec.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    });
+    match spec {
+        Some(TXT) => export_txt(path, accounts),
+        Some(YAML) => data::write_export(path, accounts, Format::Yaml),
+        Some(JSON) => data::write_export(path, accounts, Format::Json),
+        Some(TOML) => data::write_export(path, accounts, Format::Toml),
+        Some(CSV) => export_csv(path, accounts),
+        _ => Err(anyhow!("Unknown Format"))
+    }
+}
+
+fn export_txt(path: &Path, accounts: &Vector<Account>) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    for account in accounts {
+        writeln!(writer, "Name: {}", account.name)?;
+        writeln!(writer, "Username: {}", account.username)?;
+        writeln!(writer, "Password: {}", account.password.expose())?;
+        if !account.tags.is_empty() {
+            writeln!(writer, "Tags: {}", account.tags_text())?;
+        }
+        for field in account.fields.iter() {
+            writeln!(writer, "{}: {}", field.name, field.value)?;
+        }
+        writeln!(writer, "Notes:\n{}", account.notes)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn export_csv(path: &Path, accounts: &Vector<Account>) -> anyhow::Result<()> {
+    // Every custom field that is used by at least one account gets its own column.
+    let mut custom: Vec<&str> = Vec::new();
+    for field in accounts.iter().flat_map(|account| account.fields.iter()) {
+        if !custom.contains(&field.name.as_str()) {
+            custom.push(&field.name);
+        }
+    }
+    let mut writer = csv::Writer::from_path(path)?;
+    let mut header = vec!["Name", "Username", "Password", "Notes", "Tags"];
+    header.extend(custom.iter());
+    writer.write_record(&header)?;
+    for account in accounts {
+        let tags = account.tags_text();
+        let mut record = vec![
+            account.name.as_str(),
+            account.username.as_str(),
+            account.password.expose(),
+            account.notes.as_str(),
+            tags.as_str(),
+        ];
+        for name in &custom {
+            let value = account
+                .fields
+                .iter()
+                .find(|field| field.name == *name)
+                .map_or("", |field| field.value.as_str());
+            record.push(value);
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use druid::Data;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+static PREFERRED: Lazy<Option<Format>> = Lazy::new(|| {
+    let mut pargs = pico_args::Arguments::from_env();
+    match pargs.opt_value_from_str("--format") {
+        Ok(format) => format,
+        Err(err) => {
+            eprintln!("Invalid --format: {}", err);
+            std::process::exit(2)
+        }
+    }
+});
+
+/// How databases, the settings and exports are serialized.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Data, Serialize, Deserialize)]
+pub enum Format {
+    #[default]
+    Yaml,
+    Json,
+    Toml
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Yaml, Format::Json, Format::Toml];
+
+    /// The format that was requested with `--format`. Files are converted to it the next time they are written.
+    pub fn preferred() -> Option<Format> {
+        *PREFERRED
+    }
+
+    /// Guesses the format of a document based on its first line.
+    pub fn detect(content: &[u8]) -> Format {
+        let first = std::str::from_utf8(content)
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or_default();
+        if is_toml_table(first) || is_toml_key(first) {
+            Format::Toml
+        } else if first.starts_with('{') || first.starts_with('[') {
+            Format::Json
+        } else {
+            Format::Yaml
+        }
+    }
+
+    /// The file extension of documents in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Yaml => "yml",
+            Format::Json => "json",
+            Format::Toml => "toml"
+        }
+    }
+
+    pub fn write<T: Serialize, W: Write>(self, mut writer: W, value: &T) -> anyhow::Result<()> {
+        match self {
+            Format::Yaml => serde_yaml::to_writer(writer, value)?,
+            Format::Json => serde_json::to_writer_pretty(writer, value)?,
+            Format::Toml => {
+                let text = Zeroizing::new(toml::to_string(value)?);
+                writer.write_all(text.as_bytes())?
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read<T: DeserializeOwned>(self, content: &[u8]) -> anyhow::Result<T> {
+        // The messages of serde errors can quote parts of the document, which would leak passwords into the logs and popups.
+        match self {
+            Format::Yaml => serde_yaml::from_slice(content).map_err(|err| malformed(err.location().map(|loc| (loc.line(), loc.column())))),
+            Format::Json => serde_json::from_slice(content).map_err(|err| malformed(Some((err.line(), err.column())))),
+            Format::Toml => {
+                let text = std::str::from_utf8(content).map_err(|_| malformed(None))?;
+                toml::from_str(text).map_err(|err| malformed(err.span().map(|span| position(text, span.start))))
+            }
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Yaml => f.write_str("yaml"),
+            Format::Json => f.write_str("json"),
+            Format::Toml => f.write_str("toml")
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            _ => Err(anyhow!("Unknown format {}, expected yaml, json or toml", s))
+        }
+    }
+}
+
+/// An error for a document that could not be parsed, which only tells where the problem is. The messages of the parsers
+/// themselves can quote parts of the document.
+pub fn malformed(location: Option<(usize, usize)>) -> anyhow::Error {
+    match location {
+        Some((line, column)) => anyhow!("The file is malformed (line {}, column {})", line, column),
+        None => anyhow!("The file is malformed")
+    }
+}
+
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+    (line, column)
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// A line like `[settings]` or `[[accounts]]`.
+fn is_toml_table(line: &str) -> bool {
+    line.starts_with('[') && line.ends_with(']') && is_bare_key(line.trim_matches(|c| c == '[' || c == ']'))
+}
+
+/// A line like `theme = "Dark"`.
+fn is_toml_key(line: &str) -> bool {
+    line.split_once('=')
+        .map_or(false, |(key, _)| is_bare_key(key.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Entry {
+        password: String
+    }
+
+    #[test]
+    fn detect_toml() {
+        assert_eq!(Format::detect(b"[[accounts]]\nusername = \"a\""), Format::Toml);
+        assert_eq!(Format::detect(b"# comment\n\ntheme = \"Dark\""), Format::Toml);
+        assert_eq!(Format::detect(b"lock-timeout=5"), Format::Toml);
+    }
+
+    #[test]
+    fn detect_json() {
+        assert_eq!(Format::detect(b"{\n  \"theme\": \"Dark\"\n}"), Format::Json);
+        assert_eq!(Format::detect(b"  [\n  {\"username\": \"a\"}\n]"), Format::Json);
+        assert_eq!(Format::detect(b"[{\"a\": 1}]"), Format::Json);
+    }
+
+    #[test]
+    fn detect_yaml() {
+        assert_eq!(Format::detect(b"theme: Dark"), Format::Yaml);
+        assert_eq!(Format::detect(b"- username: a\n  password: b"), Format::Yaml);
+        assert_eq!(Format::detect(b"---\nkey: a = b"), Format::Yaml);
+        assert_eq!(Format::detect(b""), Format::Yaml);
+        assert_eq!(Format::detect(&[0xff, 0xfe]), Format::Yaml);
+    }
+
+    #[test]
+    fn read_each_format() {
+        let documents: [(Format, &[u8]); 3] = [
+            (Format::Yaml, b"password: hunter2"),
+            (Format::Json, b"{\"password\": \"hunter2\"}"),
+            (Format::Toml, b"password = \"hunter2\"")
+        ];
+        for (format, content) in documents {
+            let entry: Entry = format.read(content).unwrap();
+            assert_eq!(entry.password, "hunter2");
+        }
+    }
+
+    #[test]
+    fn errors_only_tell_the_position() {
+        let documents: [(Format, &[u8]); 3] = [
+            (Format::Yaml, b"\npassword: [hunter2"),
+            (Format::Json, b"{\n  \"password\": hunter2\n}"),
+            (Format::Toml, b"\npassword = hunter2")
+        ];
+        for (format, content) in documents {
+            let err = format.read::<Entry>(content).unwrap_err();
+            let message = err.to_string();
+            assert!(message.starts_with("The file is malformed (line "), "{}", message);
+            assert!(!message.contains("hunter2"), "{}", message);
+            assert!(!format!("{:?}", err).contains("hunter2"));
+        }
+        let err = Format::Json.read::<Entry>(documents[1].1).unwrap_err();
+        assert_eq!(err.to_string(), "The file is malformed (line 2, column 15)");
+    }
+
+    #[test]
+    fn positions_count_from_one() {
+        let text = "ab\ncd\nef";
+        assert_eq!(position(text, 0), (1, 1));
+        assert_eq!(position(text, 4), (2, 2));
+        assert_eq!(position(text, 6), (3, 1));
+    }
+}
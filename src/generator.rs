@@ -0,0 +1,321 @@
+use std::fmt::{Display, Formatter};
+
+use druid::{Data, Lens};
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::util::{ternary, SecretString};
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!#$%&*+-=?@^_~";
+const CONSONANTS: &str = "bcdfghjklmnprstvz";
+const VOWELS: &str = "aeiou";
+/// Characters that are easily confused with each other when a password is typed from a screen or a piece of paper.
+pub const AMBIGUOUS: &str = "Il1O0o";
+
+/// The shortest password Riot accepts.
+pub const MIN_LENGTH: u32 = 8;
+/// The longest password Riot accepts.
+pub const MAX_LENGTH: u32 = 128;
+/// The fewest words of a passphrase. Fewer words would be shorter than [`MIN_LENGTH`] or easy to guess.
+pub const MIN_WORDS: u32 = 3;
+pub const MAX_WORDS: u32 = 10;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Data, Serialize, Deserialize)]
+pub enum GeneratorMode {
+    /// Characters drawn from the enabled character classes.
+    Random,
+    /// Alternating consonants and vowels, followed by digits and/or a symbol.
+    Pronounceable,
+    /// Several made up pronounceable words separated by dashes.
+    Passphrase
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Data, Lens, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorSettings {
+    pub mode: GeneratorMode,
+    pub length: u32,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+    /// The amount of words in a passphrase.
+    pub words: u32
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            mode: GeneratorMode::Random,
+            length: 16,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: false,
+            exclude_ambiguous: true,
+            words: 4
+        }
+    }
+}
+
+impl GeneratorSettings {
+    /// Generates a password that follows Riot's rules for an account named `username`. Character classes the rules
+    /// require are added even if they are disabled: lowercase letters if no letters are enabled, and digits if neither
+    /// digits nor symbols are.
+    ///
+    /// Returns the violation of the last attempt if no password could be found, e.g. for a one letter username.
+    pub fn generate(&self, username: &str) -> Result<SecretString, RuleViolation> {
+        // The length and the character classes are right by construction, so a password only breaks the rules by
+        // chance when it happens to contain the username. Trying again is enough for any realistic username.
+        let mut violation = RuleViolation::ContainsUsername;
+        for _ in 0..100 {
+            let password = self.candidate();
+            match check_riot_rules(password.expose(), username) {
+                Ok(()) => return Ok(password),
+                Err(err) => violation = err
+            }
+        }
+        Err(violation)
+    }
+
+    /// Whether the options allow generating anything at all.
+    pub fn is_valid(&self) -> bool {
+        self.mode != GeneratorMode::Random || self.lowercase || self.uppercase || self.digits || self.symbols
+    }
+
+    fn letters(&self) -> bool {
+        self.lowercase || self.uppercase
+    }
+
+    fn digits(&self) -> bool {
+        self.digits || !self.symbols
+    }
+
+    fn length(&self) -> usize {
+        self.length.clamp(MIN_LENGTH, MAX_LENGTH) as usize
+    }
+
+    fn charset(&self, chars: &str) -> Vec<char> {
+        chars
+            .chars()
+            .filter(|c| !self.exclude_ambiguous || !AMBIGUOUS.contains(*c))
+            .collect()
+    }
+
+    fn pick(&self, chars: &str) -> char {
+        *self
+            .charset(chars)
+            .choose(&mut OsRng)
+            .expect("Character sets are never empty")
+    }
+
+    fn candidate(&self) -> SecretString {
+        SecretString::from(match self.mode {
+            GeneratorMode::Random => self.random(),
+            GeneratorMode::Pronounceable => self.pronounceable(),
+            GeneratorMode::Passphrase => self.passphrase()
+        })
+    }
+
+    fn random(&self) -> String {
+        let classes: Vec<Vec<char>> = [
+            (self.lowercase || !self.letters(), LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits(), DIGITS),
+            (self.symbols, SYMBOLS)
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, chars)| self.charset(chars))
+        .collect();
+        let all = classes.concat();
+        let length = self.length();
+        // One character of every class comes first, so that none of them can be missing by chance.
+        let mut chars: Vec<char> = Vec::with_capacity(length);
+        chars.extend(classes.iter().filter_map(|class| class.choose(&mut OsRng)));
+        while chars.len() < length {
+            chars.extend(all.choose(&mut OsRng));
+        }
+        chars.shuffle(&mut OsRng);
+        chars.into_iter().collect()
+    }
+
+    fn pronounceable(&self) -> String {
+        let length = self.length();
+        let suffix = ternary(self.digits(), 2, 0) + ternary(self.symbols, 1, 0);
+        let mut password = String::with_capacity(length);
+        while password.len() < length - suffix {
+            password.push(match password.len() % 2 {
+                0 => self.pick(CONSONANTS),
+                _ => self.pick(VOWELS)
+            });
+        }
+        if self.uppercase {
+            capitalize(&mut password);
+        }
+        for _ in 0..ternary(self.digits(), 2, 0) {
+            password.push(self.pick(DIGITS));
+        }
+        if self.symbols {
+            password.push(self.pick(SYMBOLS));
+        }
+        password
+    }
+
+    fn passphrase(&self) -> String {
+        let words = self.words.clamp(MIN_WORDS, MAX_WORDS);
+        let mut password = String::new();
+        for index in 0..words {
+            if index > 0 {
+                password.push('-');
+            }
+            let start = password.len();
+            for _ in 0..OsRng.gen_range(2..=3) {
+                password.push(self.pick(CONSONANTS));
+                password.push(self.pick(VOWELS));
+            }
+            if self.uppercase {
+                capitalize(&mut password[start..]);
+            }
+        }
+        if self.digits() {
+            password.push(self.pick(DIGITS));
+        }
+        if self.symbols {
+            password.push(self.pick(SYMBOLS));
+        }
+        password
+    }
+}
+
+fn capitalize(text: &mut str) {
+    if let Some(first) = text.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+}
+
+/// A rule of Riot's password policy. The messages are worded generally, because the same rules apply to master passwords.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Data)]
+pub enum RuleViolation {
+    TooShort,
+    TooLong,
+    MissingLetter,
+    MissingNumberOrSymbol,
+    ContainsUsername
+}
+
+impl Display for RuleViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleViolation::TooShort => write!(f, "Passwords need at least {} characters", MIN_LENGTH),
+            RuleViolation::TooLong => write!(f, "Passwords can't be longer than {} characters", MAX_LENGTH),
+            RuleViolation::MissingLetter => f.write_str("Passwords need at least one letter"),
+            RuleViolation::MissingNumberOrSymbol => f.write_str("Passwords need at least one number or symbol"),
+            RuleViolation::ContainsUsername => f.write_str("Passwords can't contain the username")
+        }
+    }
+}
+
+/// Checks `password` against the rules Riot enforces for the password of the account `username`.
+pub fn check_riot_rules(password: &str, username: &str) -> Result<(), RuleViolation> {
+    let length = password.chars().count() as u32;
+    if length < MIN_LENGTH {
+        return Err(RuleViolation::TooShort);
+    }
+    if length > MAX_LENGTH {
+        return Err(RuleViolation::TooLong);
+    }
+    if !password.chars().any(char::is_alphabetic) {
+        return Err(RuleViolation::MissingLetter);
+    }
+    if password.chars().all(char::is_alphabetic) {
+        return Err(RuleViolation::MissingNumberOrSymbol);
+    }
+    let username = username.trim().to_lowercase();
+    if !username.is_empty() && password.to_lowercase().contains(&username) {
+        return Err(RuleViolation::ContainsUsername);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_limits() {
+        assert_eq!(check_riot_rules("abcdef1", ""), Err(RuleViolation::TooShort));
+        assert_eq!(check_riot_rules("abcdefg1", ""), Ok(()));
+        assert_eq!(check_riot_rules(&format!("{}1", "a".repeat(127)), ""), Ok(()));
+        assert_eq!(check_riot_rules(&format!("{}1", "a".repeat(128)), ""), Err(RuleViolation::TooLong));
+    }
+
+    #[test]
+    fn letter_and_number_or_symbol() {
+        assert_eq!(check_riot_rules("12345678", ""), Err(RuleViolation::MissingLetter));
+        assert_eq!(check_riot_rules("abcdefgh", ""), Err(RuleViolation::MissingNumberOrSymbol));
+        assert_eq!(check_riot_rules("abcdefg!", ""), Ok(()));
+    }
+
+    #[test]
+    fn username_containment() {
+        assert_eq!(check_riot_rules("xxFaker123", "faker"), Err(RuleViolation::ContainsUsername));
+        assert_eq!(check_riot_rules("xxFaker123", " "), Ok(()));
+    }
+
+    #[test]
+    fn every_mode_follows_the_rules() {
+        // Settings that lack a class the rules require get it added.
+        let settings = [
+            GeneratorSettings::default(),
+            GeneratorSettings {
+                lowercase: false,
+                uppercase: false,
+                digits: false,
+                symbols: true,
+                ..GeneratorSettings::default()
+            },
+            GeneratorSettings {
+                digits: false,
+                length: MAX_LENGTH + 10,
+                ..GeneratorSettings::default()
+            },
+            GeneratorSettings {
+                mode: GeneratorMode::Pronounceable,
+                digits: false,
+                symbols: false,
+                length: 0,
+                ..GeneratorSettings::default()
+            },
+            GeneratorSettings {
+                mode: GeneratorMode::Passphrase,
+                digits: false,
+                symbols: false,
+                words: 1,
+                ..GeneratorSettings::default()
+            }
+        ];
+        for settings in settings {
+            for _ in 0..20 {
+                let password = settings.generate("faker").unwrap();
+                assert_eq!(check_riot_rules(password.expose(), "faker"), Ok(()), "{:?}", settings);
+            }
+        }
+    }
+
+    #[test]
+    fn impossible_usernames_are_reported() {
+        let settings = GeneratorSettings {
+            mode: GeneratorMode::Passphrase,
+            ..GeneratorSettings::default()
+        };
+        // Every passphrase contains the dashes between its words.
+        assert_eq!(settings.generate("-").unwrap_err(), RuleViolation::ContainsUsername);
+    }
+}
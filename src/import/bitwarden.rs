@@ -0,0 +1,150 @@
+use anyhow::bail;
+use druid::im::Vector;
+use serde::Deserialize;
+
+use crate::data::{Account, CustomField};
+use crate::format::malformed;
+use crate::import::Entry;
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Option<Vec<Folder>>,
+    #[serde(default)]
+    items: Option<Vec<Item>>
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    name: Option<String>,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    login: Option<Login>,
+    fields: Option<Vec<Field>>
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    uris: Option<Vec<Uri>>
+}
+
+#[derive(Deserialize)]
+struct Uri {
+    uri: Option<String>
+}
+
+#[derive(Deserialize)]
+struct Field {
+    name: Option<String>,
+    value: Option<String>
+}
+
+/// Whether `content` was exported by Bitwarden rather than by this program.
+pub fn is_export(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content).map_or(false, |value| value.get("items").is_some() || value.get("encrypted").is_some())
+}
+
+/// Reads an unencrypted JSON export of Bitwarden. Only logins are imported, cards and secure notes are skipped.
+pub fn parse(content: &str) -> anyhow::Result<Vector<Entry>> {
+    let export: Export = serde_json::from_str(content).map_err(|err| malformed(Some((err.line(), err.column()))))?;
+    if export.encrypted {
+        bail!("Encrypted Bitwarden exports are not supported. Please export as unencrypted json.");
+    }
+    let folders = export.folders.unwrap_or_default();
+    let entries = export
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|item| {
+            let login = item.login?;
+            let folder = item
+                .folder_id
+                .and_then(|id| folders.iter().find(|folder| folder.id == id))
+                .map(|folder| folder.name.clone())
+                .unwrap_or_default();
+            let fields = item
+                .fields
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|field| {
+                    Some(CustomField {
+                        name: field.name?,
+                        value: field.value.unwrap_or_default()
+                    })
+                })
+                .collect();
+            let account = Account {
+                name: item.name.unwrap_or_default(),
+                username: login.username.unwrap_or_default(),
+                password: login.password.unwrap_or_default().as_str().into(),
+                notes: item.notes.unwrap_or_default(),
+                fields,
+                ..Default::default()
+            };
+            let urls = login
+                .uris
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|uri| uri.uri)
+                .collect();
+            Some(Entry { account, folder, urls })
+        })
+        .collect();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "encrypted": false,
+        "folders": [{"id": "f1", "name": "League"}],
+        "items": [
+            {
+                "name": "main",
+                "notes": "text",
+                "folderId": "f1",
+                "login": {"username": "user", "password": "secret", "uris": [{"uri": "https://euw.leagueoflegends.com"}]},
+                "fields": [{"name": "Server", "value": "EUW"}]
+            },
+            {"name": "card", "card": {"number": "1234"}}
+        ]
+    }"#;
+
+    #[test]
+    fn parse_logins() {
+        assert!(is_export(EXPORT));
+        let entries = parse(EXPORT).unwrap();
+        // Only logins are imported.
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.folder, "League");
+        assert_eq!(entry.urls.len(), 1);
+        assert_eq!(entry.account.username, "user");
+        assert_eq!(entry.account.password.expose(), "secret");
+        assert_eq!(entry.account.fields[0].value, "EUW");
+    }
+
+    #[test]
+    fn reject_encrypted_exports() {
+        assert!(parse(r#"{"encrypted": true, "items": []}"#).is_err());
+    }
+
+    #[test]
+    fn errors_do_not_quote_the_file() {
+        let err = parse(r#"{"items": [{"login": {"password": "secret"}}], oops}"#).unwrap_err();
+        assert!(!err.to_string().contains("secret"));
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::anyhow;
+use druid::im::Vector;
+use roxmltree::{Document, Node};
+
+use crate::data::{Account, CustomField};
+use crate::format::malformed;
+use crate::import::{split_tags, Entry};
+
+/// Reads a database that was exported as "KeePass XML (2.x)". The folder of an entry is the path of its groups.
+pub fn parse(content: &str) -> anyhow::Result<Vector<Entry>> {
+    let document = Document::parse(content).map_err(|err| malformed(Some((err.pos().row as usize, err.pos().col as usize))))?;
+    let root = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("Root"))
+        .ok_or_else(|| anyhow!("This is not a KeePass XML file"))?;
+    let mut entries = Vector::new();
+    for group in root.children().filter(|node| node.has_tag_name("Group")) {
+        read_group(group, "", &mut entries);
+    }
+    Ok(entries)
+}
+
+fn read_group(group: Node, parent: &str, entries: &mut Vector<Entry>) {
+    let name = child_text(group, "Name");
+    let path = match parent.is_empty() {
+        true => name,
+        false => format!("{}/{}", parent, name)
+    };
+    for child in group.children() {
+        if child.has_tag_name("Entry") {
+            entries.push_back(read_entry(child, &path));
+        } else if child.has_tag_name("Group") {
+            read_group(child, &path, entries);
+        }
+    }
+}
+
+fn read_entry(entry: Node, folder: &str) -> Entry {
+    let mut account = Account::default();
+    let mut urls = Vector::new();
+    // Older versions of an entry are kept in a nested <History>, which is skipped because only direct children are read.
+    for string in entry.children().filter(|node| node.has_tag_name("String")) {
+        let key = child_text(string, "Key");
+        let value = child_text(string, "Value");
+        match key.as_str() {
+            "Title" => account.name = value,
+            "UserName" => account.username = value,
+            "Password" => account.password = value.as_str().into(),
+            "URL" if !value.is_empty() => urls.push_back(value),
+            "Notes" => account.notes = value,
+            _ if !value.is_empty() => account.fields.push_back(CustomField { name: key, value }),
+            _ => {}
+        }
+    }
+    split_tags(&mut account, &child_text(entry, "Tags"));
+    Entry {
+        account,
+        folder: folder.to_owned(),
+        urls
+    }
+}
+
+fn child_text(node: Node, name: &str) -> String {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nested_groups() {
+        let entries = parse(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<KeePassFile>
+  <Root>
+    <Group>
+      <Name>Database</Name>
+      <Group>
+        <Name>League</Name>
+        <Entry>
+          <Tags>lol;euw</Tags>
+          <String><Key>Title</Key><Value>main</Value></String>
+          <String><Key>UserName</Key><Value>user</Value></String>
+          <String><Key>Password</Key><Value>secret</Value></String>
+          <String><Key>URL</Key><Value>https://euw.leagueoflegends.com</Value></String>
+          <String><Key>Server</Key><Value>EUW</Value></String>
+          <History>
+            <Entry>
+              <String><Key>Password</Key><Value>old</Value></String>
+            </Entry>
+          </History>
+        </Entry>
+      </Group>
+    </Group>
+  </Root>
+</KeePassFile>"#
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.folder, "Database/League");
+        assert_eq!(entry.urls.len(), 1);
+        assert_eq!(entry.account.name, "main");
+        assert_eq!(entry.account.password.expose(), "secret");
+        assert_eq!(entry.account.tags, Vector::from(vec!["lol".to_owned(), "euw".to_owned()]));
+        assert_eq!(entry.account.fields.len(), 1);
+    }
+
+    #[test]
+    fn errors_do_not_quote_the_file() {
+        let err = parse("<KeePassFile><Root><Value>secret</Root></KeePassFile>").unwrap_err();
+        assert!(!err.to_string().contains("secret"));
+    }
+}
@@ -0,0 +1,117 @@
+mod bitwarden;
+mod keepass;
+mod onepassword;
+mod table;
+mod text;
+
+use std::path::Path;
+
+use druid::im::Vector;
+use druid::{Data, FileSpec};
+pub use table::{Column, Table};
+
+use crate::data::{self, Account};
+use crate::export::{CSV, JSON, TOML, TXT};
+
+pub const KEEPASS: FileSpec = FileSpec::new("KeePass 2 XML export", &["xml"]);
+
+/// The file types that can be read by [`read`].
+pub const FORMATS: [FileSpec; 5] = [CSV, TXT, JSON, TOML, KEEPASS];
+
+/// An account that was exported from another password manager, together with where it was kept there.
+#[derive(Debug, Clone, Data)]
+pub struct Entry {
+    pub account: Account,
+    pub folder: String,
+    pub urls: Vector<String>
+}
+
+impl Entry {
+    /// Whether the folder or one of the urls contains `filter`. Expects `filter` to be lowercase.
+    pub fn matches(&self, filter: &str) -> bool {
+        self.folder.to_lowercase().contains(filter)
+            || self
+                .urls
+                .iter()
+                .any(|url| url.to_lowercase().contains(filter))
+    }
+}
+
+/// The content of a file that is imported. Depending on the format the accounts might need some further input.
+pub enum Imported {
+    Accounts(Vector<Account>),
+    /// A spreadsheet whose columns still have to be assigned.
+    Table(Table),
+    /// The content of another password manager, which usually holds more than just game accounts.
+    Entries(Vector<Entry>)
+}
+
+/// Reads a file in any of the supported [`FORMATS`]. The format is picked based on the file extension
+/// and, for spreadsheets, the header.
+pub fn read(path: &str) -> anyhow::Result<Imported> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "json" => {
+            let content = std::fs::read_to_string(path)?;
+            match bitwarden::is_export(&content) {
+                true => Ok(Imported::Entries(bitwarden::parse(&content)?)),
+                false => Ok(Imported::Accounts(data::read_export(path)?))
+            }
+        }
+        "toml" => Ok(Imported::Accounts(data::read_export(path)?)),
+        "yml" | "yaml" => Ok(Imported::Accounts(data::read_export(path)?)),
+        "xml" => Ok(Imported::Entries(keepass::parse(&std::fs::read_to_string(path)?)?)),
+        "csv" => {
+            let table = Table::read(path)?;
+            match onepassword::is_export(&table) {
+                true => Ok(Imported::Entries(onepassword::parse(&table))),
+                false => Ok(Imported::Table(table))
+            }
+        }
+        _ => Ok(Imported::Accounts(text::parse(&std::fs::read_to_string(path)?)))
+    }
+}
+
+/// Reads the accounts of a file in any of the [`FORMATS`] without asking for any further input.
+///
+/// The columns of spreadsheets are assigned based on their header and everything that was exported from another password
+/// manager is kept, so the result should be reviewed before it is used.
+pub fn read_accounts(path: &str) -> anyhow::Result<Vector<Account>> {
+    Ok(match read(path)? {
+        Imported::Accounts(accounts) => accounts,
+        Imported::Table(table) => {
+            let columns: Vec<Column> = table
+                .header
+                .iter()
+                .map(|header| Column::guess(header))
+                .collect();
+            table.accounts(&columns)
+        }
+        Imported::Entries(entries) => entries.into_iter().map(|entry| entry.account).collect()
+    })
+}
+
+/// Whether `path` is a yaml file that was written by this program. Those are imported as a whole, including the trash.
+pub fn is_native(path: &str) -> bool {
+    is_yaml(path) && data::read_export(path).is_ok()
+}
+
+fn is_yaml(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("yml") || ext.eq_ignore_ascii_case("yaml"))
+}
+
+fn split_tags(account: &mut Account, tags: &str) {
+    for tag in tags
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+    {
+        account.add_tag(tag);
+    }
+}
@@ -0,0 +1,99 @@
+use druid::im::Vector;
+
+use crate::data::Account;
+use crate::import::{split_tags, Entry, Table};
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Column {
+    Title,
+    Url,
+    Username,
+    Password,
+    Notes,
+    Tags,
+    Vault,
+    Other
+}
+
+fn column(header: &str) -> Column {
+    match header.trim().to_lowercase().as_str() {
+        "title" => Column::Title,
+        "url" | "website" | "login url" => Column::Url,
+        "username" | "login username" => Column::Username,
+        "password" | "login password" => Column::Password,
+        "notes" | "notesplain" => Column::Notes,
+        "tags" => Column::Tags,
+        "vault" => Column::Vault,
+        _ => Column::Other
+    }
+}
+
+/// 1Password names its columns differently from a hand made spreadsheet, which is used to tell them apart.
+pub fn is_export(table: &Table) -> bool {
+    let columns: Vec<Column> = table.header.iter().map(|header| column(header)).collect();
+    [Column::Title, Column::Url, Column::Password]
+        .iter()
+        .all(|required| columns.contains(required))
+}
+
+/// Reads a CSV export of 1Password. Columns that have no equivalent, like the one time password, are dropped.
+pub fn parse(table: &Table) -> Vector<Entry> {
+    let columns: Vec<Column> = table.header.iter().map(|header| column(header)).collect();
+    table
+        .rows
+        .iter()
+        .map(|row| {
+            let mut account = Account::default();
+            let mut folder = String::new();
+            let mut urls = Vector::new();
+            for (value, column) in row.iter().zip(columns.iter()) {
+                match column {
+                    Column::Title => account.name = value.clone(),
+                    Column::Url if !value.is_empty() => urls.push_back(value.clone()),
+                    Column::Username => account.username = value.clone(),
+                    Column::Password => account.password = value.as_str().into(),
+                    Column::Notes => account.notes = value.clone(),
+                    Column::Tags => split_tags(&mut account, value),
+                    Column::Vault => folder = value.clone(),
+                    _ => {}
+                }
+            }
+            Entry { account, folder, urls }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vector<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_export() {
+        let table = Table {
+            header: strings(&["Title", "Website", "Username", "Password", "OTPAuth", "Vault", "Tags"]),
+            rows: Vector::from(vec![strings(&["main", "https://euw.leagueoflegends.com", "user", "secret", "otpauth://", "Private", "lol, euw"])])
+        };
+        assert!(is_export(&table));
+        let entries = parse(&table);
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.folder, "Private");
+        assert_eq!(entry.account.name, "main");
+        assert_eq!(entry.account.password.expose(), "secret");
+        assert_eq!(entry.account.tags.len(), 2);
+        assert!(entry.account.fields.is_empty());
+    }
+
+    #[test]
+    fn spreadsheets_are_not_exports() {
+        let table = Table {
+            header: strings(&["Name", "Username", "Password"]),
+            rows: Vector::new()
+        };
+        assert!(!is_export(&table));
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::Read;
+
+use druid::im::Vector;
+use druid::Data;
+
+use crate::data::{Account, CustomField};
+
+/// What the values of a column of a spreadsheet are used for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Data)]
+pub enum Column {
+    Ignore,
+    Name,
+    Username,
+    Password,
+    Notes,
+    Tags,
+    /// Kept as a custom field that is named after the header of the column.
+    Custom
+}
+
+impl Column {
+    pub const ALL: [Column; 7] = [
+        Column::Ignore,
+        Column::Name,
+        Column::Username,
+        Column::Password,
+        Column::Notes,
+        Column::Tags,
+        Column::Custom
+    ];
+
+    /// Makes an educated guess based on the header of the column.
+    pub fn guess(header: &str) -> Self {
+        match header.trim().to_lowercase().as_str() {
+            "" => Column::Ignore,
+            "name" | "title" | "account" | "summoner" | "summoner name" => Column::Name,
+            "username" | "user" | "login" | "login name" | "user name" => Column::Username,
+            "password" | "pass" | "pw" => Column::Password,
+            "notes" | "note" | "comment" | "comments" => Column::Notes,
+            "tags" | "tag" | "labels" => Column::Tags,
+            _ => Column::Custom
+        }
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Column::Ignore => f.write_str("Ignore"),
+            Column::Name => f.write_str("Name"),
+            Column::Username => f.write_str("Username"),
+            Column::Password => f.write_str("Password"),
+            Column::Notes => f.write_str("Notes"),
+            Column::Tags => f.write_str("Tags"),
+            Column::Custom => f.write_str("Custom field")
+        }
+    }
+}
+
+/// The content of a CSV file. The first line is treated as the header.
+#[derive(Debug, Clone, Data)]
+pub struct Table {
+    pub header: Vector<String>,
+    pub rows: Vector<Vector<String>>
+}
+
+impl Table {
+    pub fn read(path: &str) -> anyhow::Result<Self> {
+        Self::parse(File::open(path)?)
+    }
+
+    fn parse(reader: impl Read) -> anyhow::Result<Self> {
+        // Not every program writes the same amount of values into every line, so missing values are treated as empty.
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        let header = reader.headers()?.iter().map(str::to_owned).collect();
+        let mut rows = Vector::new();
+        for record in reader.records() {
+            rows.push_back(record?.iter().map(str::to_owned).collect());
+        }
+        Ok(Self { header, rows })
+    }
+
+    /// The first non-empty value of a column, to give an idea of what the column contains.
+    pub fn sample(&self, column: usize) -> String {
+        self.rows
+            .iter()
+            .filter_map(|row| row.get(column))
+            .find(|value| !value.is_empty())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Turns every row into an account. `columns` holds the meaning of each column of the table.
+    /// Rows without any mapped value are skipped.
+    pub fn accounts(&self, columns: &[Column]) -> Vector<Account> {
+        let mut accounts = Vector::new();
+        for row in self.rows.iter() {
+            let mut account = Account::default();
+            let mut empty = true;
+            for ((value, column), header) in row.iter().zip(columns).zip(self.header.iter()) {
+                if value.is_empty() || *column == Column::Ignore {
+                    continue;
+                }
+                empty = false;
+                match column {
+                    Column::Ignore => {}
+                    Column::Name => account.name = value.clone(),
+                    Column::Username => account.username = value.clone(),
+                    Column::Password => account.password = value.as_str().into(),
+                    Column::Notes => match account.notes.is_empty() {
+                        true => account.notes = value.clone(),
+                        false => account.notes = format!("{}\n{}", account.notes, value)
+                    },
+                    Column::Tags => {
+                        for tag in value
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                        {
+                            account.add_tag(tag);
+                        }
+                    }
+                    Column::Custom => account.fields.push_back(CustomField {
+                        name: header.clone(),
+                        value: value.clone()
+                    })
+                }
+            }
+            if !empty {
+                accounts.push_back(account);
+            }
+        }
+        accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_quoted_and_multiline_values() {
+        let table = Table::parse(
+            "Name,Username,Password,Notes\n\
+             main,user,\"pass,\"\"word\"\"\",\"first line\nsecond line\"\n\
+             smurf,other\n"
+                .as_bytes()
+        )
+        .unwrap();
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0][2], "pass,\"word\"");
+        assert_eq!(table.rows[0][3], "first line\nsecond line");
+        assert_eq!(table.rows[1].len(), 2);
+    }
+
+    #[test]
+    fn accounts_follow_the_columns() {
+        let table = Table::parse("Title,Login,Password,Server,Tags,Unused\nmain,user,secret,EUW,\"a, b\",x\n,,,,,\n".as_bytes()).unwrap();
+        let mut columns: Vec<Column> = table.header.iter().map(|header| Column::guess(header)).collect();
+        assert_eq!(columns[5], Column::Custom);
+        columns[5] = Column::Ignore;
+        let accounts = table.accounts(&columns);
+        // The empty row is skipped.
+        assert_eq!(accounts.len(), 1);
+        let account = &accounts[0];
+        assert_eq!(account.name, "main");
+        assert_eq!(account.username, "user");
+        assert_eq!(account.password.expose(), "secret");
+        assert_eq!(account.tags, Vector::from(vec!["a".to_owned(), "b".to_owned()]));
+        assert_eq!(account.fields.len(), 1);
+        assert_eq!(account.fields[0].name, "Server");
+        assert_eq!(account.fields[0].value, "EUW");
+    }
+}
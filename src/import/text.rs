@@ -0,0 +1,206 @@
+use druid::im::Vector;
+
+use crate::data::{Account, CustomField};
+
+enum Key<'a> {
+    Name,
+    Username,
+    Password,
+    Tags,
+    Notes,
+    Other(&'a str)
+}
+
+enum Line<'a> {
+    Value(Key<'a>, &'a str),
+    /// A line like `username:password`, which is how accounts are usually jotted down in a plain text file.
+    Credentials(&'a str, &'a str),
+    Text(&'a str)
+}
+
+fn parse_line(line: &str) -> Line<'_> {
+    let trimmed = line.trim();
+    let (key, value) = match trimmed.split_once(':') {
+        Some((key, value)) if value.is_empty() || value.starts_with(char::is_whitespace) => (key.trim(), value.trim()),
+        Some((username, password)) if !trimmed.contains(char::is_whitespace) => return Line::Credentials(username, password),
+        _ => return Line::Text(line)
+    };
+    let key = match key.to_lowercase().as_str() {
+        "name" | "title" | "account" => Key::Name,
+        "username" | "user" | "login" => Key::Username,
+        "password" | "pass" | "pw" => Key::Password,
+        "tags" => Key::Tags,
+        "notes" | "note" | "comment" => Key::Notes,
+        _ => Key::Other(key)
+    };
+    Line::Value(key, value)
+}
+
+#[derive(Default)]
+struct Parser {
+    accounts: Vector<Account>,
+    current: Account,
+    in_notes: bool,
+    blank_lines: usize
+}
+
+impl Parser {
+    fn finish_account(&mut self) {
+        let mut account = std::mem::take(&mut self.current);
+        self.in_notes = false;
+        if !account.name.is_empty() || !account.username.is_empty() || !account.password.is_empty() {
+            account.notes = account.notes.trim_end().to_owned();
+            self.accounts.push_back(account);
+        }
+    }
+
+    fn append_notes(&mut self, text: &str) {
+        if !self.current.notes.is_empty() {
+            let breaks = self.blank_lines + 1;
+            self.current.notes.push_str(&"\n".repeat(breaks));
+        }
+        self.current.notes.push_str(text);
+    }
+
+    fn line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            self.blank_lines += 1;
+            return;
+        }
+        let parsed = parse_line(line);
+        let known_key = matches!(parsed, Line::Value(ref key, _) if !matches!(key, Key::Other(_)));
+        // Blocks are separated by empty lines. Notes may contain empty lines as well, so they only end at a known key
+        // that follows an empty line.
+        if self.blank_lines > 0 && (!self.in_notes || known_key) {
+            self.finish_account();
+        } else if self.in_notes {
+            self.append_notes(line);
+            self.blank_lines = 0;
+            return;
+        }
+        self.blank_lines = 0;
+        match parsed {
+            Line::Value(key, value) => {
+                // A field that is already filled means that the next account started without an empty line in between.
+                let taken = match key {
+                    Key::Name => !self.current.name.is_empty(),
+                    Key::Username => !self.current.username.is_empty(),
+                    Key::Password => !self.current.password.is_empty(),
+                    _ => false
+                };
+                if taken {
+                    self.finish_account();
+                }
+                match key {
+                    Key::Name => self.current.name = value.to_owned(),
+                    Key::Username => self.current.username = value.to_owned(),
+                    Key::Password => self.current.password = value.into(),
+                    Key::Tags => {
+                        for tag in value
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                        {
+                            self.current.add_tag(tag);
+                        }
+                    }
+                    Key::Notes => {
+                        self.in_notes = true;
+                        if !value.is_empty() {
+                            self.append_notes(value);
+                        }
+                    }
+                    Key::Other(name) => self.current.fields.push_back(CustomField {
+                        name: name.to_owned(),
+                        value: value.to_owned()
+                    })
+                }
+            }
+            Line::Credentials(username, password) => {
+                self.finish_account();
+                self.current.name = username.to_owned();
+                self.current.username = username.to_owned();
+                self.current.password = password.into();
+                self.finish_account();
+            }
+            Line::Text(text) => self.append_notes(text.trim())
+        }
+    }
+}
+
+/// Reads accounts from a plain text file.
+///
+/// Understands the blocks of `Name:`, `Username:`, `Password:` and `Notes:` lines that are written by the TXT export,
+/// as well as hand written lists with one `username:password` per line. Unknown `Key: value` lines become custom fields.
+pub fn parse(content: &str) -> Vector<Account> {
+    let mut parser = Parser::default();
+    for line in content.lines() {
+        parser.line(line);
+    }
+    parser.finish_account();
+    parser.accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export;
+
+    #[test]
+    fn txt_export_round_trip() {
+        let accounts = Vector::from(vec![
+            Account {
+                name: "main".to_owned(),
+                username: "user".to_owned(),
+                password: "pa:ss word".into(),
+                notes: "first line\n\nServer: EUW\nlast line".to_owned(),
+                tags: Vector::from(vec!["a".to_owned(), "b".to_owned()]),
+                fields: Vector::from(vec![CustomField {
+                    name: "Email".to_owned(),
+                    value: "user@example.com".to_owned()
+                }]),
+                ..Account::default()
+            },
+            Account {
+                name: "smurf".to_owned(),
+                username: "other".to_owned(),
+                password: "secret".into(),
+                ..Account::default()
+            },
+        ]);
+        let path = std::env::temp_dir().join(format!("lam-export-{}.txt", std::process::id()));
+        export::export(&path, &accounts).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let parsed = parse(&content);
+        assert_eq!(parsed.len(), accounts.len());
+        for (parsed, account) in parsed.iter().zip(accounts.iter()) {
+            assert_eq!(
+                Account {
+                    id: account.id,
+                    ..parsed.clone()
+                },
+                *account
+            );
+        }
+    }
+
+    #[test]
+    fn parse_credentials_lines() {
+        let accounts = parse("main:secret1\nsmurf:secret2\n");
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].name, "main");
+        assert_eq!(accounts[0].username, "main");
+        assert_eq!(accounts[0].password.expose(), "secret1");
+        assert_eq!(accounts[1].username, "smurf");
+    }
+
+    #[test]
+    fn parse_blocks_without_empty_lines() {
+        let accounts = parse("Username: main\nPassword: one\nUsername: smurf\nPassword: two\nsome text");
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[1].username, "smurf");
+        assert_eq!(accounts[1].notes, "some text");
+    }
+}
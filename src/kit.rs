@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use druid::{FileDialogOptions, FileSpec};
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+
+pub const HTML: FileSpec = FileSpec::new("printable page", &["html"]);
+
+const PREFIX: &str = "LAMKIT1";
+/// The amount of base64 characters in a single QR code. Bigger codes are hard to scan from paper.
+const CHUNK_SIZE: usize = 800;
+
+/// The options for the save dialog of an emergency kit.
+pub fn dialog_options() -> FileDialogOptions {
+    FileDialogOptions::new()
+        .allowed_types(vec![HTML])
+        .default_name("emergency kit.html")
+        .default_type(HTML)
+}
+
+/// Whether `path` should be written as an emergency kit.
+pub fn is_kit(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("html"))
+}
+
+// A short fingerprint that keeps the codes of different kits apart. It has to be the same in every version of the program,
+// so the hasher of the standard library can not be used.
+fn checksum(content: &[u8]) -> String {
+    let hash = content
+        .iter()
+        .fold(0x811c9dc5u32, |hash, byte| (hash ^ *byte as u32).wrapping_mul(0x01000193));
+    format!("{:08x}", hash)
+}
+
+/// Splits `content` into lines of the form `LAMKIT1:<checksum>:<index>/<count>:<base64>`.
+fn chunks(content: &[u8]) -> Vec<String> {
+    let checksum = checksum(content);
+    let encoded = STANDARD.encode(content);
+    let parts: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).expect("base64 is ascii"))
+        .collect();
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| format!("{}:{}:{}/{}:{}", PREFIX, checksum, index + 1, parts.len(), part))
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes a printable page that holds the encrypted `content` as QR codes, together with instructions for restoring it.
+pub fn write(path: &Path, title: &str, content: &[u8]) -> anyhow::Result<()> {
+    let chunks = chunks(content);
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(
+        writer,
+        "<html><head><meta charset=\"utf-8\"><title>Emergency kit: {}</title>",
+        escape(title)
+    )?;
+    writeln!(
+        writer,
+        "<style>body{{font-family:sans-serif;margin:2em}}.code{{display:inline-block;width:45%;margin:1em;vertical-align:top;\
+         page-break-inside:avoid}}.code p{{font-family:monospace;font-size:7pt;word-break:break-all}}</style></head><body>"
+    )?;
+    writeln!(writer, "<h1>Emergency kit: {}</h1>", escape(title))?;
+    writeln!(
+        writer,
+        "<p>This page holds an encrypted copy of your accounts. It is useless without the password, but keep it somewhere safe anyway.</p>"
+    )?;
+    writeln!(writer, "<h2>Restoring</h2><ol>")?;
+    writeln!(
+        writer,
+        "<li>Install the LoL Account Manager and choose <b>Restore an emergency kit</b> on the setup screen.</li>"
+    )?;
+    writeln!(
+        writer,
+        "<li>Scan all {} codes in any order and paste their text into the field. The text is also printed below every code.</li>",
+        chunks.len()
+    )?;
+    writeln!(
+        writer,
+        "<li>Choose where the restored database should be saved and enter its password.</li>"
+    )?;
+    writeln!(writer, "</ol>")?;
+    for (index, chunk) in chunks.iter().enumerate() {
+        let image = QrCode::with_error_correction_level(chunk, EcLevel::M)?
+            .render::<svg::Color>()
+            .min_dimensions(300, 300)
+            .build();
+        // The renderer starts with an xml declaration, which does not belong into a html document.
+        let image = image
+            .find("<svg")
+            .map_or(image.as_str(), |start| &image[start..]);
+        writeln!(
+            writer,
+            "<div class=\"code\"><h3>Code {} of {}</h3>{}<p>{}</p></div>",
+            index + 1,
+            chunks.len(),
+            image,
+            chunk
+        )?;
+    }
+    writeln!(writer, "</body></html>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reassembles the encrypted file from the text of the scanned QR codes. The codes can be pasted in any order and
+/// everything that does not belong to a code is ignored.
+pub fn restore(text: &str) -> anyhow::Result<Vec<u8>> {
+    let mut kit: Option<(String, usize)> = None;
+    let mut parts = BTreeMap::new();
+    for line in text
+        .split_whitespace()
+        .filter(|line| line.starts_with(PREFIX))
+    {
+        let mut fields = line.splitn(4, ':').skip(1);
+        let (checksum, position, part) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(checksum), Some(position), Some(part)) => (checksum, position, part),
+            _ => bail!("{} is not a complete code", line)
+        };
+        let (index, count) = position
+            .split_once('/')
+            .and_then(|(index, count)| Some((index.parse::<usize>().ok()?, count.parse::<usize>().ok()?)))
+            .filter(|(index, count)| (1..=*count).contains(index))
+            .ok_or_else(|| anyhow!("{} is not a complete code", line))?;
+        match &kit {
+            Some((expected, total)) if expected != checksum || *total != count => bail!("The codes belong to different emergency kits"),
+            Some(_) => {}
+            None => kit = Some((checksum.to_owned(), count))
+        }
+        if parts.insert(index, part).is_some() {
+            bail!("Code {} of {} was pasted more than once", index, count);
+        }
+    }
+    let (expected, count) = kit.ok_or_else(|| anyhow!("No codes of an emergency kit were found"))?;
+    if let Some(missing) = (1..=count).find(|index| !parts.contains_key(index)) {
+        bail!("Code {} of {} is missing", missing, count);
+    }
+    let encoded: String = parts.values().copied().collect();
+    let content = STANDARD
+        .decode(encoded)
+        .map_err(|_| anyhow!("The codes are damaged"))?;
+    if checksum(&content) != expected {
+        bail!("The codes are damaged");
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Large enough for several codes.
+    fn content() -> Vec<u8> {
+        (0..2000u32).map(|i| (i * 7 % 256) as u8).collect()
+    }
+
+    #[test]
+    fn round_trip_in_any_order() {
+        let content = content();
+        let mut chunks = chunks(&content);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(restore(&chunks.join("\n")).unwrap(), content);
+        chunks.reverse();
+        assert_eq!(restore(&format!("scanned:\n{}\n", chunks.join("\n\n"))).unwrap(), content);
+    }
+
+    #[test]
+    fn missing_codes_are_rejected() {
+        let chunks = chunks(&content());
+        let err = restore(&chunks[1..].join("\n")).unwrap_err();
+        assert_eq!(err.to_string(), "Code 1 of 4 is missing");
+    }
+
+    #[test]
+    fn duplicate_codes_are_rejected() {
+        let mut chunks = chunks(&content());
+        chunks.push(chunks[2].clone());
+        assert!(restore(&chunks.join("\n")).is_err());
+    }
+
+    #[test]
+    fn damaged_codes_are_rejected() {
+        let mut chunks = chunks(&content());
+        let start = chunks[0].rfind(':').unwrap() + 1;
+        let replacement = match &chunks[0][start..start + 1] {
+            "A" => "B",
+            _ => "A"
+        };
+        chunks[0].replace_range(start..start + 1, replacement);
+        assert_eq!(restore(&chunks.join("\n")).unwrap_err().to_string(), "The codes are damaged");
+    }
+
+    #[test]
+    fn codes_of_different_kits_are_rejected() {
+        let first = chunks(&content());
+        let second = chunks(b"another database");
+        assert!(restore(&format!("{}\n{}", first.join("\n"), second[0])).is_err());
+    }
+}
@@ -1,8 +1,16 @@
 #![windows_subsystem = "windows"]
 
 mod data;
+mod export;
+mod format;
+mod generator;
+mod import;
+mod kit;
+mod merge;
 pub mod os;
 mod screens;
+mod store;
+mod strength;
 mod util;
 mod widgets;
 
@@ -14,9 +22,10 @@ pub fn main() {
     let window = WindowDesc::new(MainUi::widget())
         .window_size((400.0, 600.0))
         .title(LocalizedString::new("scroll-demo-window-title").with_placeholder("LoL Account Manager"));
-    AppLauncher::with_window(window)
+    let result = AppLauncher::with_window(window)
         .log_to_console()
         //.launch(AppState::Setup(SetupState::new(Settings::load().unwrap())))
-        .launch(MainUi::new())
-        .expect("launch failed");
+        .launch(MainUi::new());
+    export::delete_pending();
+    result.expect("launch failed");
 }
@@ -0,0 +1,342 @@
+use std::collections::HashSet;
+
+use druid::im::Vector;
+use druid::{Data, Lens};
+
+use crate::data::{Account, AccountId};
+
+/// An account that was changed differently on both sides. `None` means the account was deleted on that side.
+#[derive(Debug, Clone, Data)]
+pub struct Conflict {
+    pub base: Option<Account>,
+    pub local: Option<Account>,
+    pub remote: Option<Account>
+}
+
+impl Conflict {
+    pub fn id(&self) -> AccountId {
+        self.local
+            .as_ref()
+            .or(self.remote.as_ref())
+            .or(self.base.as_ref())
+            .map(|acc| acc.id)
+            .expect("A conflict involves at least one account")
+    }
+
+    pub fn name(&self) -> String {
+        self.local
+            .as_ref()
+            .or(self.remote.as_ref())
+            .or(self.base.as_ref())
+            .map(|acc| acc.name.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Data, Lens)]
+pub struct MergeResult {
+    /// The merged accounts. Conflicting accounts hold the local version until the conflict is resolved.
+    pub accounts: Vector<Account>,
+    pub conflicts: Vector<Conflict>
+}
+
+impl MergeResult {
+    /// Replaces the placeholder of a conflicting account with the chosen version or removes it when `None` was chosen.
+    pub fn resolve(&mut self, id: AccountId, choice: Option<Account>) {
+        let index = self.accounts.iter().position(|acc| acc.id == id);
+        match (index, choice) {
+            (Some(index), Some(account)) => self.accounts[index] = account,
+            (Some(index), None) => {
+                self.accounts.remove(index);
+            }
+            (None, Some(account)) => self.accounts.push_back(account),
+            (None, None) => {}
+        }
+        self.conflicts.retain(|conflict| conflict.id() != id);
+    }
+}
+
+/// Combines the changes that were made locally and remotely since both lists diverged from `base`.
+///
+/// Accounts are matched by their id. The order of the local list is kept and accounts that were only added remotely are appended.
+pub fn merge(base: &Vector<Account>, local: &Vector<Account>, remote: &Vector<Account>) -> MergeResult {
+    let find = |list: &Vector<Account>, id: AccountId| list.iter().find(|acc| acc.id == id).cloned();
+
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    for account in local.iter().chain(remote.iter()).chain(base.iter()) {
+        if seen.insert(account.id) {
+            ids.push(account.id);
+        }
+    }
+
+    let mut result = MergeResult {
+        accounts: Vector::new(),
+        conflicts: Vector::new()
+    };
+    for id in ids {
+        let base = find(base, id);
+        let local = find(local, id);
+        let remote = find(remote, id);
+        let merged = if local == remote || remote == base {
+            local.clone()
+        } else if local == base {
+            remote.clone()
+        } else {
+            result.conflicts.push_back(Conflict {
+                base,
+                local: local.clone(),
+                remote: remote.clone()
+            });
+            local.clone().or(remote.clone())
+        };
+        if let Some(account) = merged {
+            result.accounts.push_back(account);
+        }
+    }
+    result
+}
+
+/// How an imported account relates to the accounts that are already in the database.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Data)]
+pub enum Status {
+    New,
+    Changed,
+    Identical
+}
+
+/// An account from an imported file together with the account of the database that has the same username.
+#[derive(Debug, Clone, Data, Lens)]
+pub struct Incoming {
+    pub account: Account,
+    pub existing: Option<Account>,
+    pub status: Status,
+    /// Whether the account should be added or update the existing one. Identical accounts are never taken over.
+    pub include: bool
+}
+
+impl Incoming {
+    /// The names of the fields that the import would change.
+    pub fn changes(&self) -> Vec<&'static str> {
+        let existing = match &self.existing {
+            Some(existing) => existing,
+            None => return Vec::new()
+        };
+        let updated = update(existing, &self.account);
+        [
+            ("Name", existing.name != updated.name),
+            ("Password", existing.password != updated.password),
+            ("Notes", existing.notes != updated.notes),
+            ("Tags", existing.tags != updated.tags),
+            ("Fields", existing.fields != updated.fields)
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+// Usernames are what identifies an account in the game, so they are the most reliable way to find the same account in
+// two lists. Accounts without a username fall back to their name.
+fn key(account: &Account) -> String {
+    match account.username.trim() {
+        "" => account.name.trim().to_lowercase(),
+        username => username.to_lowercase()
+    }
+}
+
+/// The existing account with the values of `imported`. Tags and custom fields are combined, everything else is replaced
+/// unless the import left it empty.
+fn update(existing: &Account, imported: &Account) -> Account {
+    let mut account = existing.clone();
+    if !imported.name.is_empty() {
+        account.name = imported.name.clone();
+    }
+    if !imported.password.is_empty() {
+        account.password = imported.password.clone();
+    }
+    if !imported.notes.is_empty() {
+        account.notes = imported.notes.clone();
+    }
+    for tag in imported.tags.iter() {
+        account.add_tag(tag);
+    }
+    for field in imported.fields.iter() {
+        match account.fields.iter_mut().find(|f| f.name == field.name) {
+            Some(existing) => existing.value = field.value.clone(),
+            None => account.fields.push_back(field.clone())
+        }
+    }
+    account
+}
+
+/// Compares the accounts of an imported file with the accounts of a database.
+///
+/// Accounts are matched by their username. When a file contains the same username more than once only the first one is kept.
+pub fn compare(accounts: &Vector<Account>, imported: Vector<Account>) -> Vector<Incoming> {
+    let mut seen = HashSet::new();
+    imported
+        .into_iter()
+        .filter(|account| seen.insert(key(account)))
+        .map(|account| {
+            let existing = accounts
+                .iter()
+                .find(|acc| key(acc) == key(&account))
+                .cloned();
+            let status = match &existing {
+                None => Status::New,
+                Some(existing) if update(existing, &account) == *existing => Status::Identical,
+                Some(_) => Status::Changed
+            };
+            Incoming {
+                account,
+                existing,
+                status,
+                include: status != Status::Identical
+            }
+        })
+        .collect()
+}
+
+/// Adds the new accounts and updates the changed ones. Updated accounts keep their id and record the old values in their history.
+pub fn apply(accounts: &mut Vector<Account>, incoming: &Vector<Incoming>) {
+    for entry in incoming.iter().filter(|entry| entry.include) {
+        match entry
+            .existing
+            .as_ref()
+            .and_then(|existing| accounts.iter().position(|acc| acc.id == existing.id))
+        {
+            Some(index) => {
+                let old = accounts[index].clone();
+                let mut account = update(&old, &entry.account);
+                account.record_changes(&old);
+                accounts[index] = account;
+            }
+            None => {
+                let mut account = entry.account.clone();
+                account.id = AccountId::new();
+                account.history.clear();
+                account.normalize_tags();
+                accounts.push_back(account);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(name: &str, username: &str, password: &str) -> Account {
+        Account {
+            name: name.to_owned(),
+            username: username.to_owned(),
+            password: password.into(),
+            ..Account::default()
+        }
+    }
+
+    fn list(accounts: &[&Account]) -> Vector<Account> {
+        accounts.iter().map(|acc| (*acc).clone()).collect()
+    }
+
+    #[test]
+    fn merge_takes_changes_of_both_sides() {
+        let a = account("a", "a", "1");
+        let b = account("b", "b", "1");
+        let base = list(&[&a, &b]);
+        let local_a = Account { password: "2".into(), ..a.clone() };
+        let remote_b = Account { password: "3".into(), ..b.clone() };
+        let added = account("c", "c", "1");
+
+        let result = merge(&base, &list(&[&local_a, &b]), &list(&[&a, &remote_b, &added]));
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.accounts, list(&[&local_a, &remote_b, &added]));
+    }
+
+    #[test]
+    fn merge_reports_different_changes_as_conflict() {
+        let a = account("a", "a", "1");
+        let local = Account { password: "2".into(), ..a.clone() };
+        let remote = Account { password: "3".into(), ..a.clone() };
+
+        let mut result = merge(&list(&[&a]), &list(&[&local]), &list(&[&remote]));
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].id(), a.id);
+        assert_eq!(result.accounts, list(&[&local]));
+
+        result.resolve(a.id, Some(remote.clone()));
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.accounts, list(&[&remote]));
+    }
+
+    #[test]
+    fn merge_applies_deletes_of_one_side() {
+        let a = account("a", "a", "1");
+        let b = account("b", "b", "1");
+        let base = list(&[&a, &b]);
+
+        let result = merge(&base, &list(&[&b]), &base);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.accounts, list(&[&b]));
+
+        let result = merge(&base, &base, &list(&[&a]));
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.accounts, list(&[&a]));
+    }
+
+    #[test]
+    fn merge_reports_delete_of_changed_account_as_conflict() {
+        let a = account("a", "a", "1");
+        let remote = Account { password: "2".into(), ..a.clone() };
+
+        let mut result = merge(&list(&[&a]), &Vector::new(), &list(&[&remote]));
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].local, None);
+        assert_eq!(result.accounts, list(&[&remote]));
+
+        result.resolve(a.id, None);
+        assert!(result.accounts.is_empty());
+    }
+
+    #[test]
+    fn compare_matches_usernames() {
+        let existing = account("main", "Main", "1");
+        let accounts = list(&[&existing]);
+        let imported = Vector::from(vec![account("", "main", "1"), account("main", "MAIN", "2"), account("smurf", "smurf", "1")]);
+
+        let incoming = compare(&accounts, imported);
+        // The second entry for the same username is dropped.
+        assert_eq!(incoming.len(), 2);
+        assert_eq!(incoming[0].status, Status::Identical);
+        assert!(!incoming[0].include);
+        assert_eq!(incoming[1].status, Status::New);
+        assert!(incoming[1].include);
+    }
+
+    #[test]
+    fn compare_lists_changed_fields() {
+        let accounts = list(&[&account("main", "main", "1")]);
+        let incoming = compare(&accounts, Vector::from(vec![account("renamed", "main", "2")]));
+        assert_eq!(incoming[0].status, Status::Changed);
+        assert_eq!(incoming[0].changes(), vec!["Name", "Password"]);
+    }
+
+    #[test]
+    fn apply_updates_the_first_of_duplicate_usernames() {
+        let first = account("first", "main", "1");
+        let second = account("second", "main", "1");
+        let mut accounts = list(&[&first, &second]);
+        let incoming = compare(&accounts, Vector::from(vec![account("", "main", "2"), account("smurf", "smurf", "1")]));
+
+        apply(&mut accounts, &incoming);
+        assert_eq!(accounts.len(), 3);
+        assert_eq!(accounts[0].id, first.id);
+        assert_eq!(accounts[0].name, "first");
+        assert_eq!(accounts[0].password.expose(), "2");
+        assert_eq!(accounts[0].history.len(), 1);
+        assert_eq!(accounts[1], second);
+        assert_eq!(accounts[2].username, "smurf");
+    }
+}
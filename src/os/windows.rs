@@ -62,7 +62,7 @@ pub fn login_account(account: &Account) -> anyhow::Result<()> {
         input.push(get_keyboard_event(VK_A, 0, KEYEVENTF_KEYUP));
         input.push(get_keyboard_event(VK_LCONTROL, 0, KEYEVENTF_KEYUP));
 
-        for c in account.password.encode_utf16() {
+        for c in account.password.expose().encode_utf16() {
             input.push(get_keyboard_event(VIRTUAL_KEY::default(), c, KEYEVENTF_UNICODE));
             input.push(get_keyboard_event(VIRTUAL_KEY::default(), c, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
         }
@@ -1,20 +1,30 @@
-use std::ops::{Index, IndexMut};
-
-use druid::widget::{CrossAxisAlignment, Flex, MainAxisAlignment};
-use druid::{Data, Lens, Widget, WidgetExt};
-use druid_material_icons::normal::action::DONE;
+use druid::im::Vector;
+use druid::theme::{BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
+use druid::widget::{
+    Button, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, MainAxisAlignment, Maybe, Scope, SizedBox, Tabs, TextBox
+};
+use druid::{lens, Data, Env, Event, EventCtx, Lens, LensExt, Selector, Widget, WidgetExt};
+use druid_material_icons::normal::action::{DELETE, DONE};
 use druid_material_icons::normal::navigation::CLOSE;
+use druid_widget_nursery::ComputedWidget;
 
-use crate::data::Account;
+use crate::data::{Account, AccountId, Change, CustomField, Field};
+use crate::generator::{check_riot_rules, GeneratorSettings, RuleViolation};
 use crate::screens::edit::EditState;
 use crate::screens::popup::PopupState;
-use crate::screens::{AppState, Navigator};
-use crate::util::{field, icon_text_button, multiline_field, password_field};
+use crate::screens::{AppState, MainUi, Navigator};
+use crate::strength::rate;
+use crate::util::theme::{RED, TEXT_SIZE_SMALL};
+use crate::util::{field, generated_password_field, icon_text_button, multiline_field, strength_meter, ternary, IndexWrapper, Indexed, SecretString};
+use crate::widgets::{Icon, WidgetButton};
+
+const RESTORE_CHANGE: Selector<Change> = Selector::new("lol_account_manager_v2.account.restore");
+const REMOVE_FIELD: Selector<usize> = Selector::new("lol_account_manager_v2.account.remove_field");
 
 #[derive(Copy, Clone, Data)]
 pub enum EditMode {
     New,
-    Existing(usize)
+    Existing(AccountId)
 }
 
 #[derive(Clone, Data, Lens)]
@@ -29,21 +39,35 @@ impl AccountState {
         !self.account.name.is_empty() && !self.account.username.is_empty() && !self.account.password.is_empty()
     }
 
-    fn unsaved_changes(&self) -> bool {
+    pub fn unsaved_changes(&self) -> bool {
         match self.mode {
             EditMode::New => !self.account.name.is_empty() || !self.account.username.is_empty() || !self.account.password.is_empty(),
-            EditMode::Existing(i) => self.previous.database.accounts.index(i) != &self.account
+            EditMode::Existing(id) => self.previous.database().account(id) != Some(&self.account)
         }
     }
 
     fn save(&mut self) {
+        self.account.normalize_tags();
+        if let EditMode::Existing(id) = self.mode {
+            if let Some(old) = self.previous.database().account(id).cloned() {
+                self.account.record_changes(&old);
+            }
+        }
+        self.previous.checkpoint();
         match self.mode {
             EditMode::New => self
                 .previous
-                .database
+                .database_mut()
                 .accounts
                 .push_back(self.account.clone()),
-            EditMode::Existing(index) => *self.previous.database.accounts.index_mut(index) = self.account.clone()
+            EditMode::Existing(id) => match self.previous.database().position(id) {
+                Some(index) => self.previous.database_mut().accounts[index] = self.account.clone(),
+                None => self
+                    .previous
+                    .database_mut()
+                    .accounts
+                    .push_back(self.account.clone())
+            }
         }
     }
 
@@ -67,29 +91,50 @@ impl AccountState {
         }
     }
 
-    pub fn existing(previous: EditState, index: usize) -> Self {
-        let account = previous.database.accounts.index(index).clone();
-        Self {
+    pub fn existing(previous: EditState, id: AccountId) -> Option<Self> {
+        let account = previous.database().account(id)?.clone();
+        Some(Self {
             previous,
             account,
-            mode: EditMode::Existing(index)
-        }
+            mode: EditMode::Existing(id)
+        })
     }
 }
 
+fn open_generator(ctx: &EventCtx) {
+    ctx.get_external_handle()
+        .add_idle_callback(|ui: &mut MainUi| {
+            if let AppState::Account(state) = &ui.state {
+                let popup = PopupState::generator(ui.settings.generator.clone(), state.account.username.clone());
+                ui.open_popup(popup);
+            }
+        })
+}
+
+/// Puts the generated password into the account that is being edited and remembers the options of the generator.
+pub fn generator_confirmed(ctx: &EventCtx, password: SecretString, options: GeneratorSettings) {
+    ctx.get_external_handle()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            ui.close_popup();
+            if let AppState::Account(state) = &mut ui.state {
+                state.account.password = password;
+            }
+            if ui.settings.generator != options {
+                ui.settings.generator = options;
+                if let Err(err) = ui.settings.save() {
+                    ui.open_popup(err.into())
+                }
+            }
+        })
+}
+
 fn build_account_ui() -> impl Widget<AccountState> {
     Flex::column()
         .with_flex_child(
-            Flex::column()
-                .cross_axis_alignment(CrossAxisAlignment::Start)
-                .with_child(field("Name:").lens(Account::name))
-                .with_spacer(3.0)
-                .with_child(field("Username:").lens(Account::username))
-                .with_spacer(3.0)
-                .with_child(password_field("Password:").lens(Account::password))
-                .with_spacer(3.0)
-                .with_flex_child(multiline_field("Notes:").lens(Account::notes), 1.0)
-                .lens(AccountState::account),
+            Tabs::new()
+                .with_tab("Details", details_ui().lens(AccountState::account))
+                .with_tab("History", history_ui().lens(AccountState::account.then(Account::history)))
+                .controller(HistoryRestorer),
             1.0
         )
         .with_spacer(3.0)
@@ -119,3 +164,194 @@ fn build_account_ui() -> impl Widget<AccountState> {
         .padding(6.0)
         .expand()
 }
+
+fn details_ui() -> impl Widget<Account> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_spacer(3.0)
+        .with_child(field("Name:").lens(Account::name))
+        .with_spacer(3.0)
+        .with_child(field("Username:").lens(Account::username))
+        .with_spacer(3.0)
+        .with_child(generated_password_field("Password:", open_generator).lens(Account::password))
+        .with_child(ComputedWidget::new(strength_meter(), |account: &Account| {
+            rate(&account.password, &account.username)
+        }))
+        .with_child(ComputedWidget::new(
+            Maybe::or_empty(|| {
+                Label::dynamic(|violation: &RuleViolation, _| violation.to_string())
+                    .with_text_color(RED)
+                    .with_text_size(TEXT_SIZE_SMALL)
+                    .padding((3.0, 0.0))
+            }),
+            |account: &Account| match account.password.is_empty() {
+                true => None,
+                false => check_riot_rules(account.password.expose(), &account.username).err()
+            }
+        ))
+        .with_spacer(3.0)
+        .with_child(field("Tags:").lens(Account::tags.map(
+            |tags: &Vector<String>| tags.iter().cloned().collect::<Vec<_>>().join(","),
+            // Not trimmed here so that the text survives the round trip while typing. The tags are cleaned up on save.
+            |tags: &mut Vector<String>, text: String| {
+                *tags = match text.is_empty() {
+                    true => Vector::new(),
+                    false => text.split(',').map(str::to_owned).collect()
+                }
+            }
+        )))
+        .with_spacer(3.0)
+        .with_child(custom_fields_ui().lens(Account::fields))
+        .with_spacer(3.0)
+        .with_flex_child(multiline_field("Notes:").lens(Account::notes), 1.0)
+}
+
+fn custom_fields_ui() -> impl Widget<Vector<CustomField>> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            List::new(custom_field_ui)
+                .with_spacing(3.0)
+                .lens(lens::Identity.map(
+                    |fields: &Vector<CustomField>| IndexWrapper::from(fields.clone()),
+                    |fields: &mut Vector<CustomField>, x: IndexWrapper<CustomField>| *fields = x.into()
+                ))
+        )
+        .with_spacer(3.0)
+        .with_child(Button::new("Add field").on_click(|_, fields: &mut Vector<CustomField>, _| fields.push_back(CustomField::default())))
+        .controller(FieldRemover)
+}
+
+fn custom_field_ui() -> impl Widget<Indexed<CustomField>> {
+    Flex::row()
+        .with_child(
+            TextBox::new()
+                .with_placeholder("Field")
+                .lens(lens::Identity.map(
+                    |entry: &Indexed<CustomField>| entry.name.clone(),
+                    |entry: &mut Indexed<CustomField>, name| entry.name = name
+                ))
+                .fix_width(100.0)
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            TextBox::new()
+                .with_placeholder("Value")
+                .expand_width()
+                .lens(lens::Identity.map(
+                    |entry: &Indexed<CustomField>| entry.value.clone(),
+                    |entry: &mut Indexed<CustomField>, value| entry.value = value
+                )),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(
+            WidgetButton::new(Icon::new(DELETE).padding(3.0))
+                .on_click(|ctx, entry: &mut Indexed<CustomField>, _| ctx.submit_command(REMOVE_FIELD.with(entry.index())))
+        )
+        .padding(3.0)
+        .expand_width()
+        .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
+fn history_ui() -> impl Widget<Vector<Change>> {
+    Either::new(
+        |history: &Vector<Change>, _| history.is_empty(),
+        Label::new("This account has not been changed yet").center(),
+        List::new(change_ui)
+            .with_spacing(3.0)
+            .scroll()
+            .vertical()
+            // Newest first
+            .lens(lens::Identity.map(
+                |history: &Vector<Change>| history.iter().rev().cloned().collect::<Vector<_>>(),
+                |_, _: Vector<Change>| {}
+            ))
+            .expand()
+            .padding((0.0, 3.0, 0.0, 0.0))
+    )
+}
+
+#[derive(Clone, Data, Lens)]
+struct ChangeState {
+    change: Change,
+    visible: bool
+}
+
+fn change_ui() -> impl Widget<Change> {
+    Scope::from_lens(
+        |change: Change| ChangeState { change, visible: false },
+        ChangeState::change,
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(
+                Label::dynamic(|state: &ChangeState, _| match state.change.days_ago() {
+                    0 => format!("{} changed today", state.change.field),
+                    1 => format!("{} changed yesterday", state.change.field),
+                    days => format!("{} changed {} days ago", state.change.field, days)
+                })
+                .with_text_size(TEXT_SIZE_SMALL)
+            )
+            .with_spacer(2.0)
+            .with_child(
+                Flex::row()
+                    .with_flex_child(
+                        Label::dynamic(|state: &ChangeState, _| match state.change.field == Field::Password && !state.visible {
+                            true => "••••••••".to_string(),
+                            false => state.change.old.expose().to_owned()
+                        })
+                        .with_line_break_mode(LineBreaking::WordWrap)
+                        .expand_width(),
+                        1.0
+                    )
+                    .with_spacer(3.0)
+                    .with_child(Either::new(
+                        |state: &ChangeState, _| state.change.field == Field::Password,
+                        Button::dynamic(|visible: &bool, _| ternary(*visible, "Hide", "Show").to_string())
+                            .on_click(|_, visible: &mut bool, _| *visible = !*visible)
+                            .lens(ChangeState::visible),
+                        SizedBox::empty()
+                    ))
+                    .with_spacer(3.0)
+                    .with_child(
+                        Button::new("Restore")
+                            .on_click(|ctx, state: &mut ChangeState, _| ctx.submit_command(RESTORE_CHANGE.with(state.change.clone())))
+                    )
+            )
+    )
+    .padding(5.0)
+    .expand_width()
+    .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+    .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
+struct HistoryRestorer;
+
+impl<W: Widget<AccountState>> Controller<AccountState, W> for HistoryRestorer {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut AccountState, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some(change) = cmd.get(RESTORE_CHANGE) {
+                data.account.restore(change);
+                ctx.set_handled();
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
+
+struct FieldRemover;
+
+impl<W: Widget<Vector<CustomField>>> Controller<Vector<CustomField>, W> for FieldRemover {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut Vector<CustomField>, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some(index) = cmd.get(REMOVE_FIELD).copied() {
+                if index < data.len() {
+                    data.remove(index);
+                }
+                ctx.set_handled();
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
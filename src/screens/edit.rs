@@ -1,43 +1,312 @@
+use std::path::Path;
 use std::thread::spawn;
 
+use anyhow::anyhow;
+use druid::commands::{SAVE_FILE_AS, SHOW_SAVE_PANEL};
+use druid::im::Vector;
 use druid::theme::{BACKGROUND_LIGHT, BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
-use druid::widget::{AspectRatioBox, Container, Controller, Flex, Label, List, MainAxisAlignment};
-use druid::{lens, Data, Env, Event, EventCtx, Lens, LensExt, Selector, Widget, WidgetExt};
-use druid_material_icons::normal::action::DELETE;
-use druid_material_icons::normal::content::{ADD, SAVE};
+use druid::widget::{AspectRatioBox, Checkbox, Container, Controller, Either, Flex, Label, List, MainAxisAlignment, SizedBox};
+use druid::{
+    lens, Data, Env, Event, EventCtx, ExtEventSink, HotKey, KbKey, Lens, LensExt, LifeCycle, LifeCycleCtx, Selector, SysMods, Widget, WidgetExt
+};
+use druid_material_icons::normal::action::{DELETE, DRAG_INDICATOR, LABEL, LOCK, PRINT, RESTORE_FROM_TRASH};
+use druid_material_icons::normal::content::{ADD, FORWARD, REDO, SAVE, SELECT_ALL, UNDO};
+use druid_material_icons::normal::editor::{VERTICAL_ALIGN_BOTTOM, VERTICAL_ALIGN_TOP};
+use druid_material_icons::normal::file::FILE_DOWNLOAD;
 use druid_material_icons::normal::image::EDIT;
-use druid_material_icons::normal::navigation::{ARROW_DROP_DOWN, ARROW_DROP_UP, CLOSE};
+use druid_material_icons::normal::navigation::{ARROW_DROP_DOWN, ARROW_DROP_UP, CHEVRON_LEFT, CHEVRON_RIGHT, CLOSE};
 
-use crate::data::{Account, Database};
+use crate::data::{self, Account, AccountId, Database, DatabaseSettings, Protection};
+use crate::merge::merge;
 use crate::screens::account::AccountState;
 use crate::screens::main::MainState;
+use crate::screens::merge::MergeState;
 use crate::screens::popup::PopupState;
-use crate::screens::{AppState, Navigator};
+use crate::screens::trash::TrashState;
+use crate::screens::{AppState, MainUi, Navigator};
+use crate::util::theme::TEXT_SIZE_SMALL;
 use crate::util::{icon_text_button, IndexWrapper, Indexed};
-use crate::widgets::{Icon, WidgetButton};
+use crate::widgets::{DragHandle, Icon, Reorder, WidgetButton};
+use crate::{export, kit};
 
-const EDIT_ACCOUNT: Selector<usize> = Selector::new("lol_account_manager_v2.edit.edit");
-const DELETE_ACCOUNT: Selector<usize> = Selector::new("lol_account_manager_v2.edit.delete");
-const MOVE_ACCOUNT: Selector<(usize, i32)> = Selector::new("lol_account_manager_v2.edit.move");
+const EDIT_ACCOUNT: Selector<AccountId> = Selector::new("lol_account_manager_v2.edit.edit");
+const DELETE_ACCOUNT: Selector<AccountId> = Selector::new("lol_account_manager_v2.edit.delete");
+const MOVE_ACCOUNT: Selector<(AccountId, usize)> = Selector::new("lol_account_manager_v2.edit.move");
+const SELECT_ACCOUNT: Selector<(AccountId, bool)> = Selector::new("lol_account_manager_v2.edit.select");
+const TAKE_FOCUS: Selector = Selector::new("lol_account_manager_v2.edit.focus");
+const UNDO_LIMIT: usize = 100;
+const ROW_HEIGHT: f64 = 60.0;
+const ROW_SPACING: f64 = 3.0;
 
 #[derive(Clone, Data, Lens)]
 pub struct EditState {
     pub previous: MainState,
-    pub database: Database
+    pub databases: Vector<Database>,
+    active: usize,
+    undo: Vector<Vector<Database>>,
+    redo: Vector<Vector<Database>>,
+    trash_retention: u32,
+    selection: Vector<AccountId>,
+    /// The account that was clicked last. Shift clicks select everything between it and the clicked account.
+    anchor: Option<AccountId>
 }
 
-impl From<MainState> for EditState {
-    fn from(ms: MainState) -> Self {
+#[derive(Clone, Data)]
+struct Row {
+    account: Account,
+    selected: bool
+}
+
+impl EditState {
+    pub fn new(ms: MainState, trash_retention: u32) -> Self {
         EditState {
-            database: ms.database.clone(),
-            previous: ms
+            databases: ms.databases.clone(),
+            previous: ms,
+            active: 0,
+            undo: Vector::new(),
+            redo: Vector::new(),
+            trash_retention,
+            selection: Vector::new(),
+            anchor: None
         }
     }
-}
 
-impl EditState {
-    fn unsaved_changes(&self) -> bool {
-        self.database != self.previous.database
+    /// The database whose accounts are currently shown in the editor.
+    pub fn database(&self) -> &Database {
+        &self.databases[self.active]
+    }
+
+    pub fn database_mut(&mut self) -> &mut Database {
+        &mut self.databases[self.active]
+    }
+
+    pub fn unsaved_changes(&self) -> bool {
+        self.databases != self.previous.databases
+    }
+
+    /// Remembers the current state so that the next change can be undone.
+    pub fn checkpoint(&mut self) {
+        self.undo.push_back(self.databases.clone());
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    fn undo(&mut self) {
+        if let Some(databases) = self.undo.pop_back() {
+            self.redo
+                .push_back(std::mem::replace(&mut self.databases, databases));
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(databases) = self.redo.pop_back() {
+            self.undo
+                .push_back(std::mem::replace(&mut self.databases, databases));
+        }
+    }
+
+    fn modified(&self) -> Vec<Database> {
+        self.databases
+            .iter()
+            .zip(self.previous.databases.iter())
+            .filter(|(db, previous)| db != previous)
+            .map(|(db, _)| {
+                let mut db = db.clone();
+                db.purge_expired(self.trash_retention);
+                db
+            })
+            .collect()
+    }
+
+    fn position(&self, path: &str) -> Option<usize> {
+        self.databases.iter().position(|db| db.path == path)
+    }
+
+    /// Records that `database` was written to disk, so it no longer counts as modified.
+    fn saved(&mut self, database: Database) {
+        if let Some(index) = self.position(&database.path) {
+            self.databases[index] = database.clone();
+            // Undoing past a save should not be mistaken for a change by another program.
+            for snapshot in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+                snapshot[index].version = database.version;
+            }
+            self.previous.databases[index] = database;
+        }
+    }
+
+    /// Throws away the local changes to a database in favor of the version on disk.
+    fn reload(&mut self, remote: Database) {
+        if let Some(index) = self.position(&remote.path) {
+            self.databases[index] = remote.clone();
+            self.previous.databases[index] = remote;
+            self.undo.clear();
+            self.redo.clear();
+        }
+    }
+
+    /// Takes over the merged accounts of a database. The version on disk becomes the new reference for unsaved changes.
+    pub fn merged(&mut self, remote: Database, accounts: Vector<Account>) {
+        if let Some(index) = self.position(&remote.path) {
+            let local = &mut self.databases[index];
+            local.accounts = accounts;
+            local.version = remote.version;
+            for entry in remote.trash.iter() {
+                let id = entry.account.id;
+                if local.account(id).is_none() && !local.trash.iter().any(|trashed| trashed.account.id == id) {
+                    local.trash.push_back(entry.clone());
+                }
+            }
+            self.previous.databases[index] = remote;
+            self.undo.clear();
+            self.redo.clear();
+        }
+    }
+
+    /// Merges the version on disk into the local copy, using the state before editing as the common base.
+    /// Returns the screen for resolving conflicts if some accounts were changed on both sides.
+    fn merge_remote(&mut self, remote: Database) -> Option<MergeState> {
+        let index = self.position(&remote.path)?;
+        let result = merge(
+            &self.previous.databases[index].accounts,
+            &self.databases[index].accounts,
+            &remote.accounts
+        );
+        match result.conflicts.is_empty() {
+            true => {
+                self.merged(remote, result.accounts);
+                None
+            }
+            false => Some(MergeState::new(self.clone(), remote, result))
+        }
+    }
+
+    fn select(&mut self, offset: isize) {
+        let len = self.databases.len() as isize;
+        self.active = (self.active as isize + offset).rem_euclid(len) as usize;
+        self.clear_selection();
+    }
+
+    /// The positions of the selected accounts in the active database, in list order.
+    fn selected(&self) -> Vec<usize> {
+        self.database()
+            .accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, acc)| self.selection.contains(&acc.id))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn selected_accounts(&self) -> Vector<Account> {
+        self.selected()
+            .into_iter()
+            .map(|index| self.database().accounts[index].clone())
+            .collect()
+    }
+
+    /// Toggles a single account or, with `extend`, adds every account between the previous click and this one.
+    fn click(&mut self, id: AccountId, extend: bool) {
+        let anchor = self
+            .anchor
+            .filter(|_| extend)
+            .and_then(|anchor| self.database().position(anchor));
+        match (anchor, self.database().position(id)) {
+            (Some(anchor), Some(index)) => {
+                let range: Vec<AccountId> = self
+                    .database()
+                    .accounts
+                    .iter()
+                    .skip(anchor.min(index))
+                    .take(anchor.abs_diff(index) + 1)
+                    .map(|acc| acc.id)
+                    .collect();
+                for id in range {
+                    if !self.selection.contains(&id) {
+                        self.selection.push_back(id);
+                    }
+                }
+            }
+            _ => {
+                match self.selection.index_of(&id) {
+                    Some(index) => {
+                        self.selection.remove(index);
+                    }
+                    None => self.selection.push_back(id)
+                }
+                self.anchor = Some(id);
+            }
+        }
+    }
+
+    fn select_all(&mut self) {
+        match self.selected().len() == self.database().accounts.len() {
+            true => self.clear_selection(),
+            false => self.selection = self.database().accounts.iter().map(|acc| acc.id).collect()
+        }
+    }
+
+    fn clear_selection(&mut self) {
+        self.selection.clear();
+        self.anchor = None;
+    }
+
+    /// Removes the selected accounts from the active database and returns them.
+    fn take_selected(&mut self) -> Vector<Account> {
+        let selection = self.selection.clone();
+        let (selected, rest): (Vector<Account>, Vector<Account>) = self
+            .database()
+            .accounts
+            .iter()
+            .cloned()
+            .partition(|acc| selection.contains(&acc.id));
+        self.database_mut().accounts = rest;
+        selected
+    }
+
+    fn delete_selected(&mut self) {
+        self.checkpoint();
+        for index in self.selected().into_iter().rev() {
+            self.database_mut().move_to_trash(index);
+        }
+        self.clear_selection();
+    }
+
+    fn move_selected(&mut self, to_top: bool) {
+        self.checkpoint();
+        let selected = self.take_selected();
+        let accounts = &mut self.database_mut().accounts;
+        match to_top {
+            true => {
+                let rest = std::mem::replace(accounts, selected);
+                accounts.append(rest);
+            }
+            false => accounts.append(selected)
+        }
+    }
+
+    fn tag_selected(&mut self, tag: &str, add: bool) {
+        self.checkpoint();
+        for index in self.selected() {
+            let account = &mut self.database_mut().accounts[index];
+            match add {
+                true => account.add_tag(tag),
+                false => account.remove_tag(tag)
+            }
+        }
+    }
+
+    fn move_selected_to(&mut self, path: &str) {
+        match self.position(path) {
+            Some(target) if target != self.active => {
+                self.checkpoint();
+                let selected = self.take_selected();
+                self.databases[target].accounts.append(selected);
+                self.clear_selection();
+            }
+            _ => {}
+        }
     }
 
     pub fn widget() -> impl Widget<Self> + 'static {
@@ -68,17 +337,8 @@ fn build_edit_ui() -> impl Widget<EditState> {
                         .on_click(|ctx, state: &mut EditState, _| {
                             ctx.open_popup(PopupState::saving());
                             let handle = ctx.get_external_handle();
-                            let db = state.database.clone();
-                            state.previous.database = db.clone();
-                            spawn(move || match db.save() {
-                                Ok(()) => {
-                                    handle.close_popup();
-                                    handle.back();
-                                }
-                                Err(err) => {
-                                    handle.open_popup(err.into());
-                                }
-                            });
+                            let modified = state.modified();
+                            spawn(move || save_all(&handle, modified, None));
                         })
                         .disabled_if(|state: &EditState, _| !state.unsaved_changes())
                         .expand(),
@@ -94,34 +354,298 @@ fn build_edit_ui() -> impl Widget<EditState> {
                         .expand(),
                     1.0
                 ) //Button::new("O").expand()
+                .with_spacer(3.0)
+                .with_child(
+                    WidgetButton::new(Icon::new(UNDO).expand_height().padding(3.0))
+                        .on_click(|_, state: &mut EditState, _| state.undo())
+                        .disabled_if(|state: &EditState, _| state.undo.is_empty())
+                )
+                .with_spacer(3.0)
+                .with_child(
+                    WidgetButton::new(Icon::new(REDO).expand_height().padding(3.0))
+                        .on_click(|_, state: &mut EditState, _| state.redo())
+                        .disabled_if(|state: &EditState, _| state.redo.is_empty())
+                )
+                .with_spacer(3.0)
+                .with_child(
+                    WidgetButton::new(Icon::new(RESTORE_FROM_TRASH).expand_height().padding(3.0))
+                        .on_click(|ctx, state: &mut EditState, _| ctx.open(TrashState::new(state.clone())))
+                )
                 .expand_width()
                 .fix_height(50.0)
         )
+        .with_child(Either::new(
+            |state: &EditState, _| state.databases.len() > 1,
+            database_selector_ui().padding((0.0, 3.0, 0.0, 0.0)),
+            SizedBox::empty()
+        ))
+        .with_child(Either::new(
+            |state: &EditState, _| state.selected().is_empty(),
+            SizedBox::empty(),
+            selection_ui().padding((0.0, 3.0, 0.0, 0.0))
+        ))
         .with_spacer(3.0)
         .with_flex_child(
-            List::new(item_ui)
-                .with_spacing(3.0)
-                .scroll()
-                .vertical()
-                .lens(lens::Identity.map(
-                    |d: &EditState| IndexWrapper::from(d.database.accounts.clone()),
-                    |d: &mut EditState, x: IndexWrapper<Account>| d.database.accounts = x.into()
-                ))
-                .controller(ListController)
-                .expand()
-                .padding(3.0)
-                .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
-                .rounded(TEXTBOX_BORDER_RADIUS),
+            Reorder::new(
+                List::new(item_ui)
+                    .with_spacing(ROW_SPACING)
+                    .lens(lens::Identity.map(
+                        |d: &EditState| {
+                            let rows: Vector<Row> = d
+                                .database()
+                                .accounts
+                                .iter()
+                                .map(|account| Row {
+                                    selected: d.selection.contains(&account.id),
+                                    account: account.clone()
+                                })
+                                .collect();
+                            IndexWrapper::from(rows)
+                        },
+                        // The rows only send commands, so there is nothing to write back.
+                        |_, _: IndexWrapper<Row>| {}
+                    )),
+                ROW_HEIGHT,
+                ROW_SPACING,
+                |ctx, state: &mut EditState, from, to| {
+                    if let Some(account) = state.database().accounts.get(from) {
+                        ctx.submit_command(MOVE_ACCOUNT.with((account.id, to)))
+                    }
+                }
+            )
+            .scroll()
+            .vertical()
+            .controller(ListController)
+            .expand()
+            .padding(3.0)
+            .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+            .rounded(TEXTBOX_BORDER_RADIUS),
             1.0
         )
         .padding(5.0)
+        .controller(Shortcuts)
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Data)]
+pub enum Resolution {
+    Reload,
+    Overwrite,
+    Merge
+}
+
+/// Called by the tag popup.
+pub fn tag_confirmed(ctx: &EventCtx, tag: String, add: bool) {
+    ctx.get_external_handle()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            ui.close_popup();
+            if let AppState::Editor(state) = &mut ui.state {
+                state.tag_selected(&tag, add);
+            }
+        })
+}
+
+/// Called by the popup that asks for the database the selected accounts should be moved to.
+pub fn move_confirmed(ctx: &EventCtx, path: String) {
+    ctx.get_external_handle()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            ui.close_popup();
+            if let AppState::Editor(state) = &mut ui.state {
+                state.move_selected_to(&path);
+            }
+        })
+}
+
+/// Called by the popup that asks how the encrypted export or emergency kit at `path` should be protected.
+pub fn share_confirmed(ctx: &EventCtx, path: String, protection: Protection) {
+    let handle = ctx.get_external_handle();
+    handle
+        .clone()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            let (accounts, name) = match &ui.state {
+                AppState::Editor(state) => (state.selected_accounts(), state.database().name()),
+                _ => return ui.close_popup()
+            };
+            // Deriving the key from a passphrase takes about a second, which would freeze the window.
+            ui.open_popup(PopupState::saving());
+            spawn(move || {
+                let result = match (kit::is_kit(Path::new(&path)), &protection) {
+                    // Restoring a kit creates a database, and those can only be unlocked with a password.
+                    (true, Protection::Recipients(_)) => Err(anyhow!("Emergency kits can only be protected with a passphrase")),
+                    (true, Protection::Passphrase(_)) => {
+                        data::encrypt_shared(&accounts, &protection).and_then(|content| kit::write(Path::new(&path), &name, &content))
+                    }
+                    (false, _) => data::write_shared(&path, &accounts, &protection)
+                };
+                match result {
+                    Ok(()) => handle.close_popup(),
+                    Err(err) => handle.open_popup(err.into())
+                }
+            });
+        })
+}
+
+/// Settles a conflict between the edited database at `path` and a version that was written by another program.
+pub fn resolve_conflict(ctx: &EventCtx, path: String, resolution: Resolution) {
+    let handle = ctx.get_external_handle();
+    handle.clone().add_idle_callback(move |ui: &mut MainUi| {
+        let (local, modified) = match &ui.state {
+            AppState::Editor(state) => (
+                state
+                    .position(&path)
+                    .map(|index| state.databases[index].clone()),
+                state.modified()
+            ),
+            _ => (None, Vec::new())
+        };
+        let local = match local {
+            Some(local) => local,
+            None => return ui.close_popup()
+        };
+        ui.open_popup(PopupState::saving());
+        spawn(move || {
+            let result = match resolution {
+                // Continues the interrupted save, including the databases after the conflicting one.
+                Resolution::Overwrite => return save_all(&handle, modified, Some(&path)),
+                Resolution::Reload => Database::load(&path, &local.password).map(|remote| on_editor(&handle, move |state| state.reload(remote))),
+                Resolution::Merge => Database::load(&path, &local.password).map(|remote| {
+                    handle.add_idle_callback(move |ui: &mut MainUi| {
+                        let conflicts = match &mut ui.state {
+                            AppState::Editor(state) => state.merge_remote(remote),
+                            _ => None
+                        };
+                        if let Some(conflicts) = conflicts {
+                            ui.open(conflicts);
+                        }
+                    })
+                })
+            };
+            match result {
+                Ok(()) => handle.close_popup(),
+                Err(err) => handle.open_popup(err.into())
+            }
+        });
+    })
+}
+
+/// Saves the `modified` databases and leaves the editor once all of them are written. Stops at the first database that
+/// was changed by another program, unless it is the one at `overwrite`.
+fn save_all(handle: &ExtEventSink, modified: Vec<Database>, overwrite: Option<&str>) {
+    for mut db in modified {
+        let result = match db.changed_on_disk() {
+            Ok(true) if overwrite != Some(db.path.as_str()) => return handle.open_popup(PopupState::conflict(db.path)),
+            Ok(_) => db.save(),
+            Err(err) => Err(err)
+        };
+        match result {
+            Ok(()) => on_editor(handle, move |state| state.saved(db)),
+            Err(err) => return handle.open_popup(err.into())
+        }
+    }
+    handle.close_popup();
+    handle.back();
 }
 
-fn item_ui() -> impl Widget<Indexed<Account>> {
+fn on_editor(handle: &ExtEventSink, f: impl FnOnce(&mut EditState) + Send + 'static) {
+    handle.add_idle_callback(move |ui: &mut MainUi| {
+        if let AppState::Editor(state) = &mut ui.state {
+            f(state)
+        }
+    })
+}
+
+fn database_selector_ui() -> impl Widget<EditState> {
+    Flex::row()
+        .with_child(WidgetButton::new(Icon::new(CHEVRON_LEFT).expand_height().padding(3.0)).on_click(|_, state: &mut EditState, _| state.select(-1)))
+        .with_spacer(3.0)
+        .with_flex_child(
+            Label::dynamic(|state: &EditState, _| state.database().name())
+                .center()
+                .expand(),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(WidgetButton::new(Icon::new(CHEVRON_RIGHT).expand_height().padding(3.0)).on_click(|_, state: &mut EditState, _| state.select(1)))
+        .expand_width()
+        .fix_height(40.0)
+}
+
+fn selection_ui() -> impl Widget<EditState> {
+    let button = |icon| WidgetButton::new(Icon::new(icon).expand_height().padding(3.0));
+    Flex::row()
+        .with_child(button(SELECT_ALL).on_click(|_, state: &mut EditState, _| state.select_all()))
+        .with_spacer(3.0)
+        .with_flex_child(
+            Label::dynamic(|state: &EditState, _| format!("{} selected", state.selected().len()))
+                .center()
+                .expand(),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(button(LABEL).on_click(|ctx, _, _| ctx.open_popup(PopupState::tag())))
+        .with_spacer(3.0)
+        .with_child(button(VERTICAL_ALIGN_TOP).on_click(|_, state: &mut EditState, _| state.move_selected(true)))
+        .with_spacer(3.0)
+        .with_child(button(VERTICAL_ALIGN_BOTTOM).on_click(|_, state: &mut EditState, _| state.move_selected(false)))
+        .with_spacer(3.0)
+        .with_child(button(FILE_DOWNLOAD).on_click(|ctx, _, _| ctx.submit_command(SHOW_SAVE_PANEL.with(export::dialog_options()))))
+        .with_spacer(3.0)
+        .with_child(button(LOCK).on_click(|ctx, _, _| ctx.submit_command(SHOW_SAVE_PANEL.with(export::encrypted_dialog_options()))))
+        .with_spacer(3.0)
+        .with_child(button(PRINT).on_click(|ctx, _, _| ctx.submit_command(SHOW_SAVE_PANEL.with(kit::dialog_options()))))
+        .with_spacer(3.0)
+        .with_child(
+            button(FORWARD)
+                .on_click(|ctx, state: &mut EditState, _| {
+                    let targets: Vec<String> = state
+                        .databases
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| *index != state.active)
+                        .map(|(_, db)| db.path.clone())
+                        .collect();
+                    ctx.get_external_handle()
+                        .add_idle_callback(move |ui: &mut MainUi| {
+                            let targets = targets
+                                .iter()
+                                .map(|path| {
+                                    ui.settings
+                                        .database(path)
+                                        .cloned()
+                                        .unwrap_or_else(|| DatabaseSettings::new(path))
+                                })
+                                .collect();
+                            ui.open_popup(PopupState::move_accounts(targets))
+                        })
+                })
+                .disabled_if(|state: &EditState, _| state.databases.len() < 2)
+        )
+        .with_spacer(3.0)
+        .with_child(button(DELETE).on_click(|_, state: &mut EditState, _| state.delete_selected()))
+        .with_spacer(3.0)
+        .with_child(button(CLOSE).on_click(|_, state: &mut EditState, _| state.clear_selection()))
+        .expand_width()
+        .fix_height(40.0)
+}
+
+fn item_ui() -> impl Widget<Indexed<Row>> {
     Container::new(
         Flex::row()
+            .with_child(DragHandle::new(Icon::new(DRAG_INDICATOR).expand_height().padding(3.0)))
+            .with_spacer(3.0)
+            .with_child(
+                Checkbox::new("")
+                    .lens(lens::Identity.map(|entry: &Indexed<Row>| entry.selected, |_, _: bool| {}))
+                    .controller(SelectOnClick)
+            )
+            .with_spacer(3.0)
             .with_flex_child(
-                Label::new(|entry: &Indexed<Account>, _: &_| entry.name.to_string())
+                Flex::column()
+                    .with_child(Label::new(|entry: &Indexed<Row>, _: &_| entry.account.name.to_string()))
+                    .with_child(Either::new(
+                        |entry: &Indexed<Row>, _| entry.account.tags.is_empty(),
+                        SizedBox::empty(),
+                        Label::new(|entry: &Indexed<Row>, _: &_| entry.account.tags_text()).with_text_size(TEXT_SIZE_SMALL)
+                    ))
                     .center()
                     .expand()
                     .padding(3.0),
@@ -130,22 +654,26 @@ fn item_ui() -> impl Widget<Indexed<Account>> {
             .with_spacer(3.0)
             .with_child(
                 WidgetButton::new(Icon::new(EDIT).expand_height().padding(3.0))
-                    .on_click(|ctx, entry: &mut Indexed<Account>, _| ctx.submit_command(EDIT_ACCOUNT.with(entry.index())))
+                    .on_click(|ctx, entry: &mut Indexed<Row>, _| ctx.submit_command(EDIT_ACCOUNT.with(entry.account.id)))
             )
             .with_spacer(3.0)
             .with_child(AspectRatioBox::new(
                 Flex::column()
                     .with_flex_child(
                         WidgetButton::new(Icon::new(ARROW_DROP_UP).expand_height().center())
-                            .disabled_if(|entry: &Indexed<Account>, _: &_| entry.is_first())
-                            .on_click(|ctx, entry: &mut Indexed<Account>, _| ctx.submit_command(MOVE_ACCOUNT.with((entry.index(), -1)))),
+                            .disabled_if(|entry: &Indexed<Row>, _: &_| entry.is_first())
+                            .on_click(|ctx, entry: &mut Indexed<Row>, _| {
+                                ctx.submit_command(MOVE_ACCOUNT.with((entry.account.id, entry.index() - 1)))
+                            }),
                         1.0
                     )
                     .with_spacer(3.0)
                     .with_flex_child(
                         WidgetButton::new(Icon::new(ARROW_DROP_DOWN).expand_height().center())
-                            .disabled_if(|entry: &Indexed<Account>, _: &_| entry.is_last())
-                            .on_click(|ctx, entry: &mut Indexed<Account>, _| ctx.submit_command(MOVE_ACCOUNT.with((entry.index(), 1)))),
+                            .disabled_if(|entry: &Indexed<Row>, _: &_| entry.is_last())
+                            .on_click(|ctx, entry: &mut Indexed<Row>, _| {
+                                ctx.submit_command(MOVE_ACCOUNT.with((entry.account.id, entry.index() + 1)))
+                            }),
                         1.0
                     ),
                 1.0
@@ -153,12 +681,12 @@ fn item_ui() -> impl Widget<Indexed<Account>> {
             .with_spacer(3.0)
             .with_child(
                 WidgetButton::new(Icon::new(DELETE).expand_height().padding(3.0))
-                    .on_click(|ctx, entry: &mut Indexed<Account>, _| ctx.submit_command(DELETE_ACCOUNT.with(entry.index())))
+                    .on_click(|ctx, entry: &mut Indexed<Row>, _| ctx.submit_command(DELETE_ACCOUNT.with(entry.account.id)))
             )
     )
     .expand_width()
     .padding(8.0)
-    .fix_height(60.0)
+    .fix_height(ROW_HEIGHT)
     .background(BACKGROUND_LIGHT)
     .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
     .rounded(TEXTBOX_BORDER_RADIUS)
@@ -168,20 +696,96 @@ struct ListController;
 impl<W: Widget<EditState>> Controller<EditState, W> for ListController {
     fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut EditState, env: &Env) {
         if let Event::Command(cmd) = event {
-            if let Some(index) = cmd.get(EDIT_ACCOUNT).cloned() {
-                ctx.open(AccountState::existing(data.clone(), index));
+            if let Some(id) = cmd.get(EDIT_ACCOUNT).cloned() {
+                if let Some(state) = AccountState::existing(data.clone(), id) {
+                    ctx.open(state);
+                }
             }
-            if let Some(index) = cmd.get(DELETE_ACCOUNT).cloned() {
-                data.database.accounts.remove(index);
+            if let Some(id) = cmd.get(DELETE_ACCOUNT).cloned() {
+                if let Some(index) = data.database().position(id) {
+                    data.checkpoint();
+                    data.database_mut().move_to_trash(index);
+                }
             }
-            if let Some((index, offset)) = cmd.get(MOVE_ACCOUNT).cloned() {
-                let target = match offset.is_negative() {
-                    true => index.saturating_sub(offset.unsigned_abs() as usize),
-                    false => index.saturating_add(offset.unsigned_abs() as usize)
-                };
-                data.database.accounts.swap(index, target);
+            if let Some((id, target)) = cmd.get(MOVE_ACCOUNT).cloned() {
+                if let Some(index) = data.database().position(id) {
+                    if target != index && target < data.database().accounts.len() {
+                        data.checkpoint();
+                        let accounts = &mut data.database_mut().accounts;
+                        let account = accounts.remove(index);
+                        accounts.insert(target, account);
+                    }
+                }
+            }
+            if let Some((id, extend)) = cmd.get(SELECT_ACCOUNT).cloned() {
+                data.click(id, extend);
+            }
+            if let Some(file) = cmd.get(SAVE_FILE_AS) {
+                match export::is_encrypted(&file.path) || kit::is_kit(&file.path) {
+                    true => ctx.open_popup(PopupState::share(file.path.to_string_lossy().into_owned())),
+                    false => ctx.open_popup(PopupState::export(
+                        file.path.to_string_lossy().into_owned(),
+                        data.database().accounts.clone(),
+                        data.selected_accounts()
+                    ))
+                }
             }
         }
         child.event(ctx, event, data, env)
     }
 }
+
+/// Handles clicks on the checkbox of a row itself, because the checkbox alone can not tell whether shift was held.
+struct SelectOnClick;
+
+impl<W: Widget<Indexed<Row>>> Controller<Indexed<Row>, W> for SelectOnClick {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut Indexed<Row>, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                ctx.submit_command(SELECT_ACCOUNT.with((data.account.id, mouse.mods.shift())));
+                ctx.set_handled();
+            }
+            Event::MouseUp(_) => ctx.set_handled(),
+            _ => child.event(ctx, event, data, env)
+        }
+    }
+}
+
+struct Shortcuts;
+
+impl<W: Widget<EditState>> Controller<EditState, W> for Shortcuts {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut EditState, env: &Env) {
+        match event {
+            // Key events are only delivered along the focus chain, so the editor has to hold the focus itself.
+            Event::Command(cmd) if cmd.is(TAKE_FOCUS) => ctx.request_focus(),
+            Event::MouseDown(_) if !ctx.has_focus() => ctx.request_focus(),
+            Event::KeyDown(key) if HotKey::new(SysMods::Cmd, "z").matches(key) => {
+                data.undo();
+                ctx.set_handled();
+            }
+            Event::KeyDown(key) if HotKey::new(SysMods::Cmd, "y").matches(key) || HotKey::new(SysMods::CmdShift, "Z").matches(key) => {
+                data.redo();
+                ctx.set_handled();
+            }
+            Event::KeyDown(key) if HotKey::new(SysMods::Cmd, "a").matches(key) => {
+                data.select_all();
+                ctx.set_handled();
+            }
+            Event::KeyDown(key) if key.key == KbKey::Escape => {
+                data.clear_selection();
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, child: &mut W, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &EditState, env: &Env) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::WidgetAdded => ctx.submit_command(TAKE_FOCUS.to(ctx.widget_id())),
+            _ => {}
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
+}
@@ -0,0 +1,294 @@
+use druid::im::Vector;
+use druid::theme::{BACKGROUND_LIGHT, BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
+use druid::widget::{CrossAxisAlignment, Flex, Label, LineBreaking, List, MainAxisAlignment, TextBox};
+use druid::{lens, Data, FontDescriptor, FontFamily, FontWeight, Lens, LensExt, Widget, WidgetExt};
+use druid_material_icons::normal::action::DONE;
+use druid_material_icons::normal::navigation::ARROW_BACK;
+use druid_widget_nursery::enum_switcher::Switcher;
+use druid_widget_nursery::prism::Prism;
+use druid_widget_nursery::DropdownSelect;
+
+use crate::data::Account;
+use crate::import::{Column, Entry, Table};
+use crate::screens::setup::SetupState;
+use crate::screens::{AppState, Navigator};
+use crate::util::icon_text_button;
+use crate::util::theme::TEXT_SIZE_SMALL;
+
+const PREVIEW_SIZE: usize = 10;
+
+#[derive(Clone, Data, Lens)]
+struct Mapping {
+    header: String,
+    sample: String,
+    column: Column
+}
+
+#[derive(Clone, Data, Lens)]
+struct TableSource {
+    table: Table,
+    mappings: Vector<Mapping>
+}
+
+impl TableSource {
+    fn accounts(&self) -> Vector<Account> {
+        let columns: Vec<Column> = self.mappings.iter().map(|mapping| mapping.column).collect();
+        self.table.accounts(&columns)
+    }
+}
+
+#[derive(Clone, Data, Lens)]
+struct EntrySource {
+    entries: Vector<Entry>,
+    filter: String
+}
+
+impl EntrySource {
+    fn matching(&self) -> Vector<Entry> {
+        let filter = self.filter.trim().to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| entry.matches(&filter))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Clone, Data, Prism)]
+enum Source {
+    Table(TableSource),
+    Entries(EntrySource)
+}
+
+impl Source {
+    fn accounts(&self) -> Vector<Account> {
+        match self {
+            Source::Table(source) => source.accounts(),
+            Source::Entries(source) => source
+                .matching()
+                .into_iter()
+                .map(|entry| entry.account)
+                .collect()
+        }
+    }
+
+    fn valid(&self) -> bool {
+        match self {
+            Source::Table(source) => source
+                .mappings
+                .iter()
+                .any(|mapping| mapping.column == Column::Name),
+            Source::Entries(_) => true
+        }
+    }
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct ImportPreviewState {
+    pub previous: SetupState,
+    source: Source
+}
+
+impl ImportPreviewState {
+    /// Lets the user assign the columns of a spreadsheet before importing it.
+    pub fn table(previous: SetupState, table: Table) -> Self {
+        let mappings = table
+            .header
+            .iter()
+            .enumerate()
+            .map(|(index, header)| Mapping {
+                header: header.clone(),
+                sample: table.sample(index),
+                column: Column::guess(header)
+            })
+            .collect();
+        Self {
+            previous,
+            source: Source::Table(TableSource { table, mappings })
+        }
+    }
+
+    /// Lets the user pick the game accounts out of everything that was exported from another password manager.
+    pub fn entries(previous: SetupState, entries: Vector<Entry>) -> Self {
+        Self {
+            previous,
+            source: Source::Entries(EntrySource {
+                entries,
+                filter: String::new()
+            })
+        }
+    }
+
+    pub fn widget() -> impl Widget<Self> + 'static {
+        build_import_ui()
+    }
+}
+
+impl From<ImportPreviewState> for AppState {
+    fn from(value: ImportPreviewState) -> Self {
+        AppState::Import(value)
+    }
+}
+
+fn build_import_ui() -> impl Widget<ImportPreviewState> {
+    Flex::column()
+        .with_flex_child(
+            Switcher::new()
+                .with_variant(SourceTable, table_ui())
+                .with_variant(SourceEntries, entries_ui())
+                .lens(ImportPreviewState::source),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Label::dynamic(|state: &ImportPreviewState, _| match state.source.accounts().len() {
+                1 => "1 account will be imported".to_string(),
+                n => format!("{} accounts will be imported", n)
+            })
+            .center()
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .main_axis_alignment(MainAxisAlignment::SpaceEvenly)
+                .with_flex_child(
+                    icon_text_button(DONE, "Import")
+                        .on_click(|ctx, state: &mut ImportPreviewState, _| match state.previous.import(state.source.accounts()) {
+                            Ok(db) => state.previous.finish(ctx, db),
+                            Err(err) => ctx.open_popup(err.into())
+                        })
+                        .disabled_if(|state: &ImportPreviewState, _| !state.source.valid())
+                        .expand(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    icon_text_button(ARROW_BACK, "Back")
+                        .on_click(|ctx, _, _| ctx.back())
+                        .expand(),
+                    1.0
+                )
+                .expand_width()
+                .fix_height(50.0)
+        )
+        .padding(5.0)
+}
+
+fn table_ui() -> impl Widget<TableSource> {
+    Flex::column()
+        .with_child(
+            Label::new("What do the columns contain?")
+                .center()
+                .padding(3.0)
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            List::new(mapping_ui)
+                .with_spacing(3.0)
+                .scroll()
+                .vertical()
+                .lens(TableSource::mappings)
+                .expand()
+                .padding(3.0)
+                .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+                .rounded(TEXTBOX_BORDER_RADIUS),
+            2.0
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            List::new(|| {
+                Label::dynamic(|account: &Account, _| format!("{} ({})", account.name, account.username))
+                    .with_text_size(TEXT_SIZE_SMALL)
+                    .align_left()
+            })
+            .scroll()
+            .vertical()
+            .lens(lens::Identity.map(|source: &TableSource| source.accounts().take(PREVIEW_SIZE), |_, _: Vector<Account>| {}))
+            .expand()
+            .padding(3.0)
+            .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+            .rounded(TEXTBOX_BORDER_RADIUS),
+            1.0
+        )
+}
+
+fn mapping_ui() -> impl Widget<Mapping> {
+    Flex::row()
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Label::dynamic(|mapping: &Mapping, _| mapping.header.clone())
+                        .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                )
+                .with_child(
+                    Label::dynamic(|mapping: &Mapping, _| match mapping.column {
+                        Column::Password => "••••••••".to_string(),
+                        _ => mapping.sample.clone()
+                    })
+                    .with_line_break_mode(LineBreaking::Clip)
+                    .with_text_size(TEXT_SIZE_SMALL)
+                )
+                .expand_width(),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(
+            DropdownSelect::new(
+                Column::ALL
+                    .iter()
+                    .map(|column| (column.to_string(), *column))
+            )
+            .lens(Mapping::column)
+            .fix_width(130.0)
+        )
+        .padding(5.0)
+        .background(BACKGROUND_LIGHT)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
+fn entries_ui() -> impl Widget<EntrySource> {
+    Flex::column()
+        .with_child(
+            TextBox::new()
+                .with_placeholder("Folder or website, like League or riotgames.com")
+                .expand_width()
+                .lens(EntrySource::filter)
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            List::new(entry_ui)
+                .with_spacing(3.0)
+                .scroll()
+                .vertical()
+                .lens(lens::Identity.map(|source: &EntrySource| source.matching(), |_, _: Vector<Entry>| {}))
+                .expand()
+                .padding(3.0)
+                .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+                .rounded(TEXTBOX_BORDER_RADIUS),
+            1.0
+        )
+}
+
+fn entry_ui() -> impl Widget<Entry> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::dynamic(|entry: &Entry, _| {
+            format!("{} ({})", entry.account.name, entry.account.username)
+        }))
+        .with_child(
+            Label::dynamic(|entry: &Entry, _| {
+                let urls: Vec<&str> = entry.urls.iter().map(String::as_str).collect();
+                match entry.folder.is_empty() {
+                    true => urls.join(", "),
+                    false => format!("{}  {}", entry.folder, urls.join(", "))
+                }
+            })
+            .with_line_break_mode(LineBreaking::Clip)
+            .with_text_size(TEXT_SIZE_SMALL)
+        )
+        .padding(5.0)
+        .expand_width()
+        .background(BACKGROUND_LIGHT)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
@@ -0,0 +1,220 @@
+use std::sync::Arc;
+use std::thread::spawn;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use druid::im::Vector;
+use druid::widget::{Button, Controller, Flex, Label, LineBreaking, MainAxisAlignment};
+use druid::{Data, Env, Event, EventCtx, Lens, TimerToken, Widget, WidgetExt, WindowState};
+use druid_material_icons::normal::action::LOCK;
+
+use crate::data::Database;
+use crate::screens::main::MainState;
+use crate::screens::{AppState, MainUi, Navigator};
+use crate::store::SecretStoreKind;
+use crate::util::{password_field, SecretString};
+use crate::widgets::Icon;
+
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Data, Lens)]
+pub struct LockState {
+    path: String,
+    password: SecretString,
+    store: Option<SecretStoreKind>,
+    opened: Vector<Database>,
+    others: Vector<(String, Option<SecretStoreKind>)>,
+    /// The screen that was open when the database was locked. It is shown again instead of reloading the databases.
+    resume: Option<Arc<AppState>>,
+    unlocking: bool,
+    failed_attempts: u32
+}
+
+impl LockState {
+    pub fn new(path: String, store: Option<SecretStoreKind>) -> Self {
+        Self {
+            path,
+            password: SecretString::default(),
+            store,
+            opened: Vector::new(),
+            others: Vector::new(),
+            resume: None,
+            unlocking: false,
+            failed_attempts: 0
+        }
+    }
+
+    /// Databases that were open next to the locked one. They get reopened with their saved passwords after unlocking,
+    /// the ones without a saved password are unlocked one after another.
+    pub fn reopen(self, others: Vector<(String, Option<SecretStoreKind>)>) -> Self {
+        Self { others, ..self }
+    }
+
+    /// Shows `screen` again after unlocking, for screens that hold unsaved changes.
+    pub fn resume(self, screen: AppState) -> Self {
+        Self {
+            resume: Some(Arc::new(screen)),
+            ..self
+        }
+    }
+
+    /// Databases that stay open while this one gets unlocked.
+    pub fn alongside(self, opened: Vector<Database>) -> Self {
+        Self { opened, ..self }
+    }
+
+    pub fn widget() -> impl Widget<Self> + 'static {
+        build_lock_ui()
+    }
+
+    fn unlock(&mut self, ctx: &EventCtx, password: Option<SecretString>) {
+        self.unlocking = true;
+        let handle = ctx.get_external_handle();
+        let path = self.path.clone();
+        let store = self.store.map(SecretStoreKind::get);
+        let mut databases = self.opened.clone();
+        let mut others = self.others.clone();
+        let resume = self.resume.clone();
+        spawn(move || {
+            let manual = password.is_some();
+            let database = match (password, store) {
+                (Some(password), _) => Ok(password),
+                (None, Some(store)) => store
+                    .get(&path)
+                    .and_then(|pw| pw.ok_or_else(|| anyhow!("There is no saved password for this database"))),
+                (None, None) => Err(anyhow!("Passwords are not saved for this database"))
+            }
+            .and_then(|pw| Database::load(&path, &pw));
+            match database {
+                Ok(database) => {
+                    if let Some(store) = store.filter(|_| manual) {
+                        if let Err(err) = store.store(&database.path, &database.password) {
+                            handle.open_popup(err.into());
+                        }
+                    }
+                    if let Some(screen) = resume {
+                        handle.open((*screen).clone());
+                        return;
+                    }
+                    databases.push_back(database);
+                    while let Some((path, store)) = others.pop_front() {
+                        match store.map(|store| load_saved(&path, store)) {
+                            Some(Ok(database)) => databases.push_back(database),
+                            failed => {
+                                if let Some(Err(err)) = failed {
+                                    handle.open_popup(err.into());
+                                }
+                                handle.open(LockState::new(path, store).alongside(databases).reopen(others));
+                                return;
+                            }
+                        }
+                    }
+                    handle.open(MainState::new(databases))
+                }
+                Err(err) => {
+                    handle.open_popup(err.into());
+                    handle.add_idle_callback(|ui: &mut MainUi| {
+                        if let AppState::Locked(state) = &mut ui.state {
+                            state.unlocking = false;
+                            state.failed_attempts += 1;
+                        }
+                    });
+                }
+            }
+        });
+    }
+}
+
+fn load_saved(path: &str, store: SecretStoreKind) -> anyhow::Result<Database> {
+    let password = store
+        .get()
+        .get(path)?
+        .ok_or_else(|| anyhow!("There is no saved password for {}", path))?;
+    Database::load(path, &password)
+}
+
+impl From<LockState> for AppState {
+    fn from(value: LockState) -> Self {
+        AppState::Locked(value)
+    }
+}
+
+fn build_lock_ui() -> impl Widget<LockState> {
+    Flex::column()
+        .main_axis_alignment(MainAxisAlignment::Center)
+        .must_fill_main_axis(true)
+        .with_child(Icon::new(LOCK).fix_size(48.0, 48.0))
+        .with_spacer(6.0)
+        .with_child(Label::new("The database is locked"))
+        .with_spacer(3.0)
+        .with_child(
+            Label::dynamic(|state: &LockState, _| state.path.clone())
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .center()
+        )
+        .with_spacer(6.0)
+        .with_child(password_field("Password:").lens(LockState::password))
+        .with_spacer(3.0)
+        .with_child(Label::dynamic(|state: &LockState, _| match state.failed_attempts {
+            0 => String::new(),
+            1 => "1 failed attempt".to_string(),
+            n => format!("{} failed attempts", n)
+        }))
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Unlock")
+                .on_click(|ctx, state: &mut LockState, _| {
+                    let password = state.password.clone();
+                    state.unlock(ctx, Some(password));
+                })
+                .disabled_if(|state: &LockState, _| state.unlocking || state.password.is_empty())
+                .expand_width()
+                .fix_height(50.0)
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Use saved password")
+                .on_click(|ctx, state: &mut LockState, _| state.unlock(ctx, None))
+                .disabled_if(|state: &LockState, _| state.unlocking || state.store.is_none())
+                .expand_width()
+        )
+        .padding(6.0)
+        .expand()
+}
+
+pub struct AutoLock {
+    timer: TimerToken,
+    last_input: Instant
+}
+
+impl AutoLock {
+    pub fn new() -> Self {
+        Self {
+            timer: TimerToken::INVALID,
+            last_input: Instant::now()
+        }
+    }
+
+    fn should_lock(&self, ctx: &EventCtx, ui: &MainUi) -> bool {
+        let idle = ui.settings.lock_timeout > 0 && self.last_input.elapsed() >= Duration::from_secs(60 * ui.settings.lock_timeout as u64);
+        let minimized = ui.settings.lock_on_minimize && ctx.window().get_window_state() == WindowState::Minimized;
+        idle || minimized
+    }
+}
+
+impl<W: Widget<MainUi>> Controller<MainUi, W> for AutoLock {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut MainUi, env: &Env) {
+        match event {
+            Event::WindowConnected => self.timer = ctx.request_timer(LOCK_CHECK_INTERVAL),
+            Event::Timer(token) if *token == self.timer => {
+                if self.should_lock(ctx, data) {
+                    data.lock();
+                }
+                self.timer = ctx.request_timer(LOCK_CHECK_INTERVAL);
+            }
+            Event::MouseDown(_) | Event::MouseMove(_) | Event::Wheel(_) | Event::KeyDown(_) => self.last_input = Instant::now(),
+            _ => {}
+        }
+        child.event(ctx, event, data, env)
+    }
+}
@@ -1,31 +1,72 @@
+use std::collections::{HashMap, HashSet};
+use std::thread::spawn;
+use std::time::{Duration, SystemTime};
+
 use druid::im::Vector;
 use druid::theme::{BACKGROUND_LIGHT, BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
-use druid::widget::{Button, Flex, List, TextBox};
-use druid::{lens, Application, Data, Lens, LensExt, TextAlignment, Widget, WidgetExt};
-use druid_material_icons::normal::action::SETTINGS;
+use druid::widget::{Button, Controller, Flex, Label, List, Maybe, TextBox, ZStack};
+use druid::{
+    lens, Application, Data, Env, Event, EventCtx, Lens, LensExt, Selector, Target, TextAlignment, TimerToken, UnitPoint, Widget, WidgetExt
+};
+use druid_material_icons::normal::action::{LOCK, SETTINGS};
+use druid_material_icons::normal::file::FOLDER_OPEN;
 use druid_material_icons::normal::image::EDIT;
 
-use crate::data::{Account, Database};
+use crate::data::{Account, Database, DatabaseSettings};
 use crate::os;
 use crate::screens::edit::EditState;
+use crate::screens::popup::PopupState;
 use crate::screens::settings::SettingsState;
 use crate::screens::{AppState, MainUi, Navigator};
+use crate::util::theme::TEXT_SIZE_SMALL;
 use crate::widgets::{Icon, WidgetButton};
 
 #[derive(Clone, Data, Lens)]
 pub struct MainState {
     pub filter: String,
-    pub database: Database
+    pub databases: Vector<Database>
 }
 
 impl MainState {
-    pub fn new(database: Database) -> Self {
+    pub fn new(databases: Vector<Database>) -> Self {
         Self {
             filter: "".to_string(),
-            database
+            databases
         }
     }
 
+    /// The database that was opened first. Settings and exports refer to this one.
+    pub fn primary(&self) -> &Database {
+        self.databases
+            .front()
+            .expect("The main screen requires at least one database")
+    }
+
+    fn entries(&self) -> Vector<Entry> {
+        let filter = self.filter.to_lowercase();
+        let filter = filter.as_str();
+        let badges = self.databases.len() > 1;
+        self.databases
+            .iter()
+            .flat_map(move |db| {
+                let badge = badges.then(|| db.name());
+                db.accounts
+                    .iter()
+                    .filter(move |acc| {
+                        acc.name.to_lowercase().contains(filter)
+                            || acc
+                                .tags
+                                .iter()
+                                .any(|tag| tag.to_lowercase().contains(filter))
+                    })
+                    .map(move |acc| Entry {
+                        account: acc.clone(),
+                        badge: badge.clone()
+                    })
+            })
+            .collect()
+    }
+
     pub fn widget() -> impl Widget<Self> + 'static {
         build_main_ui()
     }
@@ -58,8 +99,41 @@ fn build_main_ui() -> impl Widget<MainState> {
                 )
                 .with_spacer(3.0)
                 .with_child(
-                    WidgetButton::new(Icon::new(EDIT).expand_height().padding(3.0))
-                        .on_click(|ctx, state: &mut MainState, _| ctx.open(EditState::from(state.clone())))
+                    WidgetButton::new(Icon::new(FOLDER_OPEN).expand_height().padding(3.0)).on_click(|ctx, state: &mut MainState, _| {
+                        let open: Vector<String> = state.databases.iter().map(|db| db.path.clone()).collect();
+                        ctx.get_external_handle()
+                            .add_idle_callback(move |ui: &mut MainUi| {
+                                let recent = ui
+                                    .settings
+                                    .databases
+                                    .iter()
+                                    .filter(|db| !open.contains(&db.path))
+                                    .cloned()
+                                    .collect();
+                                let others = open
+                                    .skip(1)
+                                    .into_iter()
+                                    .map(|path| {
+                                        ui.settings
+                                            .database(&path)
+                                            .cloned()
+                                            .unwrap_or_else(|| DatabaseSettings::new(&path))
+                                    })
+                                    .collect();
+                                ui.open_popup(PopupState::switch_database(recent, others))
+                            })
+                    })
+                )
+                .with_spacer(3.0)
+                .with_child(
+                    WidgetButton::new(Icon::new(EDIT).expand_height().padding(3.0)).on_click(|ctx, state: &mut MainState, _| {
+                        let state = state.clone();
+                        ctx.get_external_handle()
+                            .add_idle_callback(|ui: &mut MainUi| {
+                                let retention = ui.settings.trash_retention;
+                                ui.open(EditState::new(state, retention))
+                            })
+                    })
                 )
                 .with_spacer(3.0)
                 .with_child(
@@ -67,13 +141,13 @@ fn build_main_ui() -> impl Widget<MainState> {
                         let state = state.clone();
                         ctx.get_external_handle()
                             .add_idle_callback(|ui: &mut MainUi| {
-                                ui.open(SettingsState {
-                                    previous: state,
-                                    settings: ui.settings.clone()
-                                })
+                                let settings = ui.settings.clone();
+                                ui.open(SettingsState::new(state, settings))
                             })
                     })
                 )
+                .with_spacer(3.0)
+                .with_child(WidgetButton::new(Icon::new(LOCK).expand_height().padding(3.0)).on_click(|ctx, _, _| ctx.lock()))
                 .expand_width()
                 .fix_height(50.0)
         )
@@ -83,17 +157,7 @@ fn build_main_ui() -> impl Widget<MainState> {
                 .with_spacing(3.0)
                 .scroll()
                 .vertical()
-                .lens(lens::Identity.map(
-                    |d: &MainState| {
-                        d.database
-                            .accounts
-                            .iter()
-                            .filter(|acc| acc.name.to_lowercase().contains(&d.filter.to_lowercase()))
-                            .cloned()
-                            .collect()
-                    },
-                    |_, _: Vector<Account>| {}
-                ))
+                .lens(lens::Identity.map(MainState::entries, |_, _: Vector<Entry>| {}))
                 .expand()
                 .padding(3.0)
                 .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
@@ -103,18 +167,133 @@ fn build_main_ui() -> impl Widget<MainState> {
         .padding(5.0)
 }
 
-fn item_ui() -> impl Widget<Account> {
-    Button::new(|item: &Account, _: &_| item.name.to_string())
-        .on_click(|ctx, acc: &mut Account, _| match os::login_account(acc) {
-            Ok(()) => ctx
-                .get_external_handle()
-                .add_idle_callback(|ui: &mut MainUi| {
-                    if ui.settings.close_on_login {
-                        Application::global().quit();
-                    }
-                }),
-            Err(err) => ctx.open_popup(err.into())
+#[derive(Clone, Data, Lens)]
+struct Entry {
+    account: Account,
+    badge: Option<String>
+}
+
+fn item_ui() -> impl Widget<Entry> {
+    ZStack::new(
+        Button::new(|item: &Entry, _: &_| item.account.name.to_string())
+            .on_click(|ctx, entry: &mut Entry, _| match os::login_account(&entry.account) {
+                Ok(()) => ctx
+                    .get_external_handle()
+                    .add_idle_callback(|ui: &mut MainUi| {
+                        if ui.settings.close_on_login {
+                            Application::global().quit();
+                        }
+                    }),
+                Err(err) => ctx.open_popup(err.into())
+            })
+            .expand()
+    )
+    .with_aligned_child(
+        Maybe::or_empty(|| {
+            Label::dynamic(|badge: &String, _| badge.clone())
+                .with_text_size(TEXT_SIZE_SMALL)
+                .padding((6.0, 2.0))
+                .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+                .rounded(8.0)
+                .padding((0.0, 0.0, 8.0, 0.0))
         })
-        .expand()
-        .height(50.0)
+        .lens(Entry::badge),
+        UnitPoint::RIGHT
+    )
+    .height(50.0)
+}
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+const DATABASE_RELOADED: Selector<Database> = Selector::new("lol_account_manager_v2.main.reloaded");
+/// Sent when a database was checked without getting a new version. Carries the modification time of the file if it
+/// failed to load.
+const DATABASE_UNCHANGED: Selector<(String, Option<SystemTime>)> = Selector::new("lol_account_manager_v2.main.unchanged");
+
+/// Reloads open databases when their files get changed by another program, e.g. a sync client.
+pub struct WatchFiles {
+    timer: TimerToken,
+    /// Databases that are being checked in the background.
+    reloading: HashSet<String>,
+    /// The modification times of files that failed to load. They are tried again once the file changes.
+    failed: HashMap<String, SystemTime>
+}
+
+impl WatchFiles {
+    pub fn new() -> Self {
+        Self {
+            timer: TimerToken::INVALID,
+            reloading: HashSet::new(),
+            failed: HashMap::new()
+        }
+    }
+
+    fn reload(&mut self, ctx: &EventCtx, db: &Database) {
+        self.reloading.insert(db.path.clone());
+        let handle = ctx.get_external_handle();
+        let db = db.clone();
+        let failed = self.failed.get(&db.path).copied();
+        // Checking for changes can mean reading and hashing the whole file, so it does not happen on the ui thread.
+        spawn(move || {
+            let modified = std::fs::metadata(&db.path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            let result = match modified.is_some() && modified == failed {
+                true => Ok(None),
+                false => db.changed_on_disk().and_then(|changed| match changed {
+                    true => Database::load(&db.path, &db.password).map(Some),
+                    false => Ok(None)
+                })
+            };
+            let submitted = match result {
+                Ok(Some(db)) => handle.submit_command(DATABASE_RELOADED, db, Target::Auto),
+                Ok(None) => handle.submit_command(DATABASE_UNCHANGED, (db.path, failed), Target::Auto),
+                Err(err) => {
+                    handle.open_popup(err.into());
+                    handle.submit_command(DATABASE_UNCHANGED, (db.path, modified), Target::Auto)
+                }
+            };
+            submitted.unwrap_or_else(|err| println!("Could not submit the reloaded database: {}", err));
+        });
+    }
+}
+
+impl<W: Widget<MainUi>> Controller<MainUi, W> for WatchFiles {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut MainUi, env: &Env) {
+        match event {
+            Event::WindowConnected => self.timer = ctx.request_timer(WATCH_INTERVAL),
+            Event::Timer(token) if *token == self.timer => {
+                // Other screens either hold unsaved changes or check for conflicts themselves before saving.
+                if let AppState::Main(state) = &data.state {
+                    for db in state.databases.iter() {
+                        if !self.reloading.contains(&db.path) {
+                            self.reload(ctx, db);
+                        }
+                    }
+                }
+                self.timer = ctx.request_timer(WATCH_INTERVAL);
+            }
+            Event::Command(cmd) if cmd.is(DATABASE_RELOADED) => {
+                let db = cmd.get_unchecked(DATABASE_RELOADED);
+                self.reloading.remove(&db.path);
+                self.failed.remove(&db.path);
+                if let AppState::Main(state) = &mut data.state {
+                    if let Some(index) = state.databases.iter().position(|open| open.path == db.path) {
+                        state.databases[index] = db.clone();
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(DATABASE_UNCHANGED) => {
+                let (path, failed) = cmd.get_unchecked(DATABASE_UNCHANGED);
+                self.reloading.remove(path);
+                match failed {
+                    Some(modified) => self.failed.insert(path.clone(), *modified),
+                    None => self.failed.remove(path)
+                };
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+        child.event(ctx, event, data, env)
+    }
 }
@@ -0,0 +1,160 @@
+use druid::theme::{BACKGROUND_LIGHT, BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
+use druid::widget::{Button, Controller, CrossAxisAlignment, Flex, Label, LineBreaking, List, MainAxisAlignment};
+use druid::{Data, Env, Event, EventCtx, FontDescriptor, FontFamily, FontWeight, Lens, LensExt, Selector, Widget, WidgetExt};
+use druid_material_icons::normal::action::DONE;
+use druid_material_icons::normal::navigation::CLOSE;
+
+use crate::data::{Account, AccountId, Database};
+use crate::merge::{Conflict, MergeResult};
+use crate::screens::edit::EditState;
+use crate::screens::{AppState, Navigator};
+use crate::util::icon_text_button;
+
+const RESOLVE_CONFLICT: Selector<(AccountId, Option<Account>)> = Selector::new("lol_account_manager_v2.merge.resolve");
+
+#[derive(Clone, Data, Lens)]
+pub struct MergeState {
+    pub previous: EditState,
+    remote: Database,
+    result: MergeResult
+}
+
+impl MergeState {
+    pub fn new(previous: EditState, remote: Database, result: MergeResult) -> Self {
+        Self { previous, remote, result }
+    }
+
+    pub fn widget() -> impl Widget<Self> + 'static {
+        build_merge_ui()
+    }
+}
+
+impl From<MergeState> for AppState {
+    fn from(value: MergeState) -> Self {
+        AppState::Merge(value)
+    }
+}
+
+fn build_merge_ui() -> impl Widget<MergeState> {
+    Flex::column()
+        .with_child(
+            Label::dynamic(|state: &MergeState, _| match state.result.conflicts.len() {
+                0 => "All conflicts are resolved".to_string(),
+                1 => format!("1 account was changed both here and in {}", state.remote.name()),
+                n => format!("{} accounts were changed both here and in {}", n, state.remote.name())
+            })
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .center()
+            .padding(3.0)
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            List::new(conflict_ui)
+                .with_spacing(3.0)
+                .scroll()
+                .vertical()
+                .lens(MergeState::result.then(MergeResult::conflicts))
+                .expand()
+                .padding(3.0)
+                .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+                .rounded(TEXTBOX_BORDER_RADIUS),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .main_axis_alignment(MainAxisAlignment::SpaceEvenly)
+                .with_flex_child(
+                    icon_text_button(DONE, "Apply")
+                        .on_click(|ctx, state: &mut MergeState, _| {
+                            let mut editor = state.previous.clone();
+                            editor.merged(state.remote.clone(), state.result.accounts.clone());
+                            ctx.open(editor);
+                        })
+                        .disabled_if(|state: &MergeState, _| !state.result.conflicts.is_empty())
+                        .expand(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    icon_text_button(CLOSE, "Cancel")
+                        .on_click(|ctx, _, _| ctx.back())
+                        .expand(),
+                    1.0
+                )
+                .expand_width()
+                .fix_height(50.0)
+        )
+        .padding(5.0)
+        .controller(ConflictResolver)
+}
+
+fn conflict_ui() -> impl Widget<Conflict> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Label::dynamic(|conflict: &Conflict, _| conflict.name())
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_flex_child(side_ui("This computer", |conflict| &conflict.local), 1.0)
+                .with_spacer(3.0)
+                .with_flex_child(side_ui("On disk", |conflict| &conflict.remote), 1.0)
+        )
+        .padding(8.0)
+        .expand_width()
+        .background(BACKGROUND_LIGHT)
+        .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
+fn side_ui(title: &str, side: fn(&Conflict) -> &Option<Account>) -> impl Widget<Conflict> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new(title))
+        .with_spacer(3.0)
+        .with_child(
+            Label::dynamic(move |conflict: &Conflict, _| describe(conflict.base.as_ref(), side(conflict).as_ref()))
+                .with_line_break_mode(LineBreaking::WordWrap)
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Keep this")
+                .on_click(move |ctx, conflict: &mut Conflict, _| ctx.submit_command(RESOLVE_CONFLICT.with((conflict.id(), side(conflict).clone()))))
+                .expand_width()
+        )
+}
+
+fn describe(base: Option<&Account>, account: Option<&Account>) -> String {
+    match account {
+        None => "Deleted".to_string(),
+        Some(account) => {
+            let password = match base {
+                Some(base) if base.password == account.password => "unchanged",
+                Some(_) => "changed",
+                None => "new"
+            };
+            format!(
+                "Name: {}\nUsername: {}\nPassword: {}\nNotes: {}",
+                account.name, account.username, password, account.notes
+            )
+        }
+    }
+}
+
+struct ConflictResolver;
+
+impl<W: Widget<MergeState>> Controller<MergeState, W> for ConflictResolver {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut MergeState, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some((id, choice)) = cmd.get(RESOLVE_CONFLICT).cloned() {
+                data.result.resolve(id, choice);
+                ctx.set_handled();
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use druid::im::Vector;
+use druid::theme::{BACKGROUND_LIGHT, BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
+use druid::widget::{Checkbox, CrossAxisAlignment, Flex, Label, LineBreaking, List, MainAxisAlignment};
+use druid::{Data, FontDescriptor, FontFamily, FontWeight, Lens, Widget, WidgetExt};
+use druid_material_icons::normal::action::DONE;
+use druid_material_icons::normal::navigation::CLOSE;
+
+use crate::data::Account;
+use crate::merge::{self, Incoming, Status};
+use crate::screens::edit::EditState;
+use crate::screens::settings::SettingsState;
+use crate::screens::{AppState, Navigator};
+use crate::util::icon_text_button;
+use crate::util::theme::TEXT_SIZE_SMALL;
+
+#[derive(Clone, Data, Lens)]
+pub struct MergeImportState {
+    pub previous: SettingsState,
+    file: String,
+    incoming: Vector<Incoming>
+}
+
+impl MergeImportState {
+    /// Compares the accounts that were read from `path` with the primary database.
+    pub fn new(previous: SettingsState, path: &Path, accounts: Vector<Account>) -> Self {
+        let incoming = merge::compare(&previous.previous.primary().accounts, accounts);
+        let file = path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        Self { previous, file, incoming }
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.incoming
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    }
+
+    /// Opens the editor with the included accounts merged into the primary database, so that they can be reviewed
+    /// or undone before anything is saved.
+    fn apply(&self) -> EditState {
+        let mut editor = EditState::new(self.previous.previous.clone(), self.previous.settings.trash_retention);
+        editor.checkpoint();
+        merge::apply(&mut editor.database_mut().accounts, &self.incoming);
+        editor
+    }
+
+    pub fn widget() -> impl Widget<Self> + 'static {
+        build_merge_import_ui()
+    }
+}
+
+impl From<MergeImportState> for AppState {
+    fn from(value: MergeImportState) -> Self {
+        AppState::MergeImport(value)
+    }
+}
+
+fn build_merge_import_ui() -> impl Widget<MergeImportState> {
+    Flex::column()
+        .with_child(
+            Label::dynamic(|state: &MergeImportState, _| {
+                format!(
+                    "{} contains {} new, {} changed and {} identical accounts",
+                    state.file,
+                    state.count(Status::New),
+                    state.count(Status::Changed),
+                    state.count(Status::Identical)
+                )
+            })
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .center()
+            .padding(3.0)
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            List::new(incoming_ui)
+                .with_spacing(3.0)
+                .scroll()
+                .vertical()
+                .lens(MergeImportState::incoming)
+                .expand()
+                .padding(3.0)
+                .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+                .rounded(TEXTBOX_BORDER_RADIUS),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Label::new("The accounts are merged in the editor, where they can be checked before saving.")
+                .with_text_size(TEXT_SIZE_SMALL)
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .center()
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .main_axis_alignment(MainAxisAlignment::SpaceEvenly)
+                .with_flex_child(
+                    icon_text_button(DONE, "Merge")
+                        .on_click(|ctx, state: &mut MergeImportState, _| ctx.open(state.apply()))
+                        .disabled_if(|state: &MergeImportState, _| !state.incoming.iter().any(|entry| entry.include))
+                        .expand(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    icon_text_button(CLOSE, "Cancel")
+                        .on_click(|ctx, _, _| ctx.back())
+                        .expand(),
+                    1.0
+                )
+                .expand_width()
+                .fix_height(50.0)
+        )
+        .padding(5.0)
+}
+
+fn incoming_ui() -> impl Widget<Incoming> {
+    Flex::row()
+        .with_child(
+            Checkbox::new("")
+                .lens(Incoming::include)
+                .disabled_if(|entry: &Incoming, _| entry.status == Status::Identical)
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    Label::dynamic(|entry: &Incoming, _| format!("{} ({})", entry.account.name, entry.account.username))
+                        .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                )
+                .with_child(
+                    Label::dynamic(|entry: &Incoming, _| match entry.status {
+                        Status::New => "New account".to_string(),
+                        Status::Changed => format!("Changes: {}", entry.changes().join(", ")),
+                        Status::Identical => "Already in the database".to_string()
+                    })
+                    .with_line_break_mode(LineBreaking::WordWrap)
+                    .with_text_size(TEXT_SIZE_SMALL)
+                )
+                .expand_width(),
+            1.0
+        )
+        .padding(5.0)
+        .background(BACKGROUND_LIGHT)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
@@ -1,10 +1,15 @@
 mod account;
 mod edit;
+mod import;
+mod lock;
 mod main;
+mod merge;
+mod merge_import;
 mod popup;
 mod settings;
 mod setup;
 mod start;
+mod trash;
 
 use druid::theme::BACKGROUND_DARK;
 use druid::widget::{Maybe, ZStack};
@@ -15,11 +20,16 @@ use druid_widget_nursery::prism::Prism;
 use crate::data::{Settings, Theme};
 use crate::screens::account::AccountState;
 use crate::screens::edit::EditState;
-use crate::screens::main::MainState;
+use crate::screens::import::ImportPreviewState;
+use crate::screens::lock::{AutoLock, LockState};
+use crate::screens::main::{MainState, WatchFiles};
+use crate::screens::merge::MergeState;
+use crate::screens::merge_import::MergeImportState;
 use crate::screens::popup::PopupState;
 use crate::screens::settings::SettingsState;
 use crate::screens::setup::SetupState;
 use crate::screens::start::StartupState;
+use crate::screens::trash::TrashState;
 use crate::util::theme::setup_theme;
 
 pub trait Navigator {
@@ -27,6 +37,7 @@ pub trait Navigator {
     fn open_popup(self, popup: PopupState);
     fn back(self);
     fn open(self, screen: impl Into<AppState>);
+    fn lock(self);
 }
 
 impl Navigator for &mut MainUi {
@@ -52,6 +63,31 @@ impl Navigator for &mut MainUi {
     fn open(self, screen: impl Into<AppState>) {
         self.state = screen.into();
     }
+
+    fn lock(self) {
+        if let Some(PopupState::Saving(_)) = self.popup {
+            return;
+        }
+        if let Some(main) = self.state.main_state() {
+            let path = main.primary().path.clone();
+            let store = self.settings.password_store(&path);
+            // Reloading the databases after unlocking would throw away unsaved changes, so such screens are kept as they are.
+            let lock = match self.unsaved_changes() {
+                true => LockState::new(path, store).resume(self.state.clone()),
+                false => {
+                    let others = main
+                        .databases
+                        .iter()
+                        .skip(1)
+                        .map(|db| (db.path.clone(), self.settings.password_store(&db.path)))
+                        .collect();
+                    LockState::new(path, store).reopen(others)
+                }
+            };
+            self.close_popup();
+            self.state = lock.into();
+        }
+    }
 }
 
 impl Navigator for &ExtEventSink {
@@ -71,6 +107,10 @@ impl Navigator for &ExtEventSink {
         let screen = screen.into();
         self.add_idle_callback(|ui: &mut MainUi| ui.open(screen))
     }
+
+    fn lock(self) {
+        self.add_idle_callback(|ui: &mut MainUi| ui.lock())
+    }
 }
 
 impl Navigator for &EventCtx<'_, '_> {
@@ -89,6 +129,10 @@ impl Navigator for &EventCtx<'_, '_> {
     fn open(self, screen: impl Into<AppState>) {
         self.get_external_handle().open(screen)
     }
+
+    fn lock(self) {
+        self.get_external_handle().lock()
+    }
 }
 
 #[derive(Clone, Data, Lens)]
@@ -110,6 +154,19 @@ impl MainUi {
         }
     }
 
+    /// Whether the current screen holds changes that would get lost by leaving it.
+    fn unsaved_changes(&self) -> bool {
+        match &self.state {
+            AppState::Settings(state) => state.settings != self.settings,
+            AppState::MergeImport(state) => state.previous.settings != self.settings,
+            AppState::Editor(state) => state.unsaved_changes(),
+            AppState::Account(state) => state.unsaved_changes() || state.previous.unsaved_changes(),
+            AppState::Merge(state) => state.previous.unsaved_changes(),
+            AppState::Trash(state) => state.previous.unsaved_changes(),
+            _ => false
+        }
+    }
+
     fn current_theme(&self) -> Theme {
         match &self.state {
             AppState::Settings(state) => state.settings.theme,
@@ -122,6 +179,8 @@ impl MainUi {
         let popup = Maybe::or_empty(PopupState::widget).lens(MainUi::popup);
         ZStack::new(main)
             .with_centered_child(popup)
+            .controller(AutoLock::new())
+            .controller(WatchFiles::new())
             .env_scope(|env, ui: &MainUi| setup_theme(ui.current_theme(), env))
     }
 }
@@ -133,7 +192,12 @@ pub enum AppState {
     Settings(SettingsState),
     Editor(EditState),
     Account(AccountState),
-    Setup(SetupState)
+    Setup(SetupState),
+    Locked(LockState),
+    Merge(MergeState),
+    Trash(TrashState),
+    Import(ImportPreviewState),
+    MergeImport(MergeImportState)
 }
 
 impl AppState {
@@ -145,6 +209,11 @@ impl AppState {
             .with_variant(AppStateAccount, AccountState::widget())
             .with_variant(AppStateSetup, SetupState::widget())
             .with_variant(AppStateStart, StartupState::widget())
+            .with_variant(AppStateLocked, LockState::widget())
+            .with_variant(AppStateMerge, MergeState::widget())
+            .with_variant(AppStateTrash, TrashState::widget())
+            .with_variant(AppStateImport, ImportPreviewState::widget())
+            .with_variant(AppStateMergeImport, MergeImportState::widget())
             .background(BACKGROUND_DARK)
     }
 
@@ -153,6 +222,23 @@ impl AppState {
             AppState::Settings(state) => Some(state.previous.clone().into()),
             AppState::Editor(state) => Some(state.previous.clone().into()),
             AppState::Account(state) => Some(state.previous.clone().into()),
+            AppState::Merge(state) => Some(state.previous.clone().into()),
+            AppState::Trash(state) => Some(state.previous.clone().into()),
+            AppState::Import(state) => Some(state.previous.clone().into()),
+            AppState::MergeImport(state) => Some(state.previous.clone().into()),
+            _ => None
+        }
+    }
+
+    fn main_state(&self) -> Option<&MainState> {
+        match self {
+            AppState::Main(state) => Some(state),
+            AppState::Settings(state) => Some(&state.previous),
+            AppState::Editor(state) => Some(&state.previous),
+            AppState::Account(state) => Some(&state.previous.previous),
+            AppState::Merge(state) => Some(&state.previous.previous),
+            AppState::Trash(state) => Some(&state.previous.previous),
+            AppState::MergeImport(state) => Some(&state.previous.previous),
             _ => None
         }
     }
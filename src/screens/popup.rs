@@ -1,21 +1,148 @@
+use std::path::Path;
+
+use druid::im::Vector;
 use druid::theme::BACKGROUND_DARK;
-use druid::widget::{BackgroundBrush, Button, Controller, Flex, Label, LineBreaking, Spinner};
-use druid::{Application, Color, Data, Env, Event, EventCtx, FontDescriptor, FontFamily, FontWeight, Widget, WidgetExt};
+use druid::widget::{
+    BackgroundBrush, Button, Checkbox, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, Maybe, RadioGroup, SizedBox,
+    Spinner, Stepper, TextBox
+};
+use druid::{lens, Application, Color, Data, Env, Event, EventCtx, FontDescriptor, FontFamily, FontWeight, Lens, LensExt, Widget, WidgetExt};
+use druid_material_icons::normal::content::ADD;
+use druid_material_icons::normal::navigation::CLOSE;
 use druid_widget_nursery::enum_switcher::Switcher;
 use druid_widget_nursery::prism::Prism;
+use druid_widget_nursery::ComputedWidget;
 
-use crate::screens::Navigator;
+use crate::data::{Account, AccountId, DatabaseSettings, Protection};
+use crate::export::{self, ExportOptions};
+use crate::generator::{GeneratorMode, GeneratorSettings, RuleViolation, AMBIGUOUS, MAX_LENGTH, MAX_WORDS, MIN_LENGTH, MIN_WORDS};
+use crate::kit;
+use crate::screens::account::generator_confirmed;
+use crate::screens::edit::{move_confirmed, resolve_conflict, share_confirmed, tag_confirmed, Resolution};
+use crate::screens::settings::{change_password_confirmed, unlock_confirmed};
+use crate::screens::setup::{check_new_password, SetupState, VerificationError};
+use crate::screens::start::{forget_confirmed, StartupState};
+use crate::screens::trash::purge_confirmed;
+use crate::screens::{AppState, MainUi, Navigator};
+use crate::util::theme::{RED, TEXT_SIZE_SMALL};
+use crate::strength::rate;
+use crate::util::{password_field, strength_meter, SecretString};
+use crate::widgets::{Icon, WidgetButton};
 
 #[derive(Clone, Data, Prism)]
 pub enum PopupState {
     Leave(()),
     Saving(bool),
-    Error(String)
+    Error(String),
+    Databases(DatabasesPopup),
+    Conflict(String),
+    MissingDatabase(String),
+    Purge(Option<AccountId>),
+    Tag(String),
+    MoveAccounts(Vector<DatabaseSettings>),
+    Share(SharePopup),
+    Unlock(UnlockPopup),
+    Export(ExportPopup),
+    Generator(GeneratorPopup),
+    ChangePassword(ChangePasswordPopup)
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct DatabasesPopup {
+    /// Recently used databases that are not open right now.
+    recent: Vector<DatabaseSettings>,
+    /// Databases that are open next to the primary one.
+    open: Vector<DatabaseSettings>
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct SharePopup {
+    path: String,
+    /// Whether the file is encrypted for age public keys instead of a passphrase.
+    recipients: bool,
+    passphrase: SecretString,
+    keys: String
+}
+
+impl SharePopup {
+    /// Emergency kits are always protected with a passphrase, because the restore screen only asks for one.
+    fn is_kit(&self) -> bool {
+        kit::is_kit(Path::new(&self.path))
+    }
+
+    fn uses_recipients(&self) -> bool {
+        self.recipients && !self.is_kit()
+    }
+
+    fn protection(&self) -> Protection {
+        match self.uses_recipients() {
+            true => Protection::Recipients(self.keys.clone()),
+            false => Protection::Passphrase(self.passphrase.clone())
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self.uses_recipients() {
+            true => self.keys.trim().is_empty(),
+            false => self.passphrase.is_empty()
+        }
+    }
+}
+
+#[derive(Clone, Data, Default, Lens)]
+pub struct ChangePasswordPopup {
+    current: SecretString,
+    password1: SecretString,
+    password2: SecretString
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct UnlockPopup {
+    path: String,
+    secret: SecretString
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct ExportPopup {
+    path: String,
+    all: Vector<Account>,
+    selected: Vector<Account>,
+    options: ExportOptions
+}
+
+impl ExportPopup {
+    fn confirmed(&self, ctx: &EventCtx) {
+        ctx.close_popup();
+        let accounts = match self.options.selected_only {
+            true => &self.selected,
+            false => &self.all
+        };
+        let path = Path::new(&self.path);
+        match export::export(path, &self.options.apply(accounts)) {
+            Ok(()) if self.options.delete_after > 0 => export::delete_later(path.to_path_buf(), self.options.delete_after),
+            Ok(()) => {}
+            Err(err) => ctx.open_popup(err.into())
+        }
+    }
+}
+
+#[derive(Clone, Data, Lens)]
+pub struct GeneratorPopup {
+    options: GeneratorSettings,
+    username: String,
+    /// The generated password, or why none could be generated.
+    password: Result<SecretString, RuleViolation>
+}
+
+impl GeneratorPopup {
+    fn regenerate(&mut self) {
+        self.password = self.options.generate(&self.username);
+    }
 }
 
 impl From<anyhow::Error> for PopupState {
     fn from(value: anyhow::Error) -> Self {
-        eprintln!("{}\n{}", value, value.backtrace());
+        // Errors are not logged, because the messages of some sources could contain parts of the passwords.
         Self::Error(value.to_string())
     }
 }
@@ -29,11 +156,80 @@ impl PopupState {
         Self::Leave(())
     }
 
+    pub fn switch_database(recent: Vector<DatabaseSettings>, open: Vector<DatabaseSettings>) -> Self {
+        Self::Databases(DatabasesPopup { recent, open })
+    }
+
+    pub fn conflict(path: String) -> Self {
+        Self::Conflict(path)
+    }
+
+    /// Tells the user that the database at `path` does not exist and offers to forget it.
+    pub fn missing_database(path: String) -> Self {
+        Self::MissingDatabase(path)
+    }
+
+    pub fn tag() -> Self {
+        Self::Tag(String::new())
+    }
+
+    pub fn move_accounts(targets: Vector<DatabaseSettings>) -> Self {
+        Self::MoveAccounts(targets)
+    }
+
+    pub fn share(path: String) -> Self {
+        Self::Share(SharePopup {
+            path,
+            recipients: false,
+            passphrase: SecretString::default(),
+            keys: String::new()
+        })
+    }
+
+    /// Asks what should be written to the unencrypted export at `path`.
+    pub fn export(path: String, all: Vector<Account>, selected: Vector<Account>) -> Self {
+        let options = ExportOptions::new(!selected.is_empty());
+        Self::Export(ExportPopup {
+            path,
+            all,
+            selected,
+            options
+        })
+    }
+
+    /// Generates a password for the account `username`.
+    pub fn generator(options: GeneratorSettings, username: String) -> Self {
+        let password = options.generate(&username);
+        Self::Generator(GeneratorPopup { options, username, password })
+    }
+
+    pub fn unlock(path: String) -> Self {
+        Self::Unlock(UnlockPopup {
+            path,
+            secret: SecretString::default()
+        })
+    }
+
+    pub fn change_password() -> Self {
+        Self::ChangePassword(ChangePasswordPopup::default())
+    }
+
     pub fn widget() -> impl Widget<Self> + 'static {
         Switcher::new()
             .with_variant(PopupStateLeave, leave_popup())
             .with_variant(PopupStateSaving, saving_popup())
             .with_variant(PopupStateError, error_popup())
+            .with_variant(PopupStateDatabases, databases_popup())
+            .with_variant(PopupStateConflict, conflict_popup())
+            .with_variant(PopupStateMissingDatabase, missing_database_popup())
+            .with_variant(PopupStatePurge, purge_popup())
+            .with_variant(PopupStateTag, tag_popup())
+            .with_variant(PopupStateMoveAccounts, move_accounts_popup())
+            .with_variant(PopupStateShare, share_popup())
+            .with_variant(PopupStateUnlock, unlock_popup())
+            .with_variant(PopupStateExport, export_popup())
+            .with_variant(PopupStateGenerator, generator_popup())
+            .with_variant(PopupStateChangePassword, change_password_popup())
             .center()
             .background(BackgroundBrush::Color(Color::rgba8(0, 0, 0, 128)))
             .expand()
@@ -72,6 +268,192 @@ fn error_popup() -> impl Widget<String> + 'static {
         .rounded(5.0)
 }
 
+fn databases_popup() -> impl Widget<DatabasesPopup> + 'static {
+    Flex::column()
+        .with_child(
+            Label::new("Switch Database")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_flex_child(
+            Flex::column()
+                .with_child(
+                    List::new(|| {
+                        Flex::row()
+                            .with_flex_child(
+                                Button::dynamic(|db: &DatabaseSettings, _| db.display_name())
+                                    .on_click(|ctx, db: &mut DatabaseSettings, _| {
+                                        ctx.close_popup();
+                                        ctx.open(StartupState::open(db.path.clone()));
+                                    })
+                                    .expand_width(),
+                                1.0
+                            )
+                            .with_spacer(3.0)
+                            .with_child(
+                                WidgetButton::new(Icon::new(ADD).padding(3.0))
+                                    .on_click(|ctx, db: &mut DatabaseSettings, _| {
+                                        let path = db.path.clone();
+                                        ctx.get_external_handle()
+                                            .add_idle_callback(move |ui: &mut MainUi| {
+                                                ui.close_popup();
+                                                if let AppState::Main(state) = &ui.state {
+                                                    let opened = state.databases.clone();
+                                                    ui.open(StartupState::add(path, opened));
+                                                }
+                                            })
+                                    })
+                                    .fix_size(30.0, 30.0)
+                            )
+                    })
+                    .with_spacing(3.0)
+                    .lens(DatabasesPopup::recent)
+                )
+                .with_child(Either::new(
+                    |popup: &DatabasesPopup, _| popup.open.is_empty(),
+                    SizedBox::empty(),
+                    Flex::column()
+                        .with_spacer(5.0)
+                        .with_child(Label::new("Also open"))
+                        .with_spacer(3.0)
+                        .with_child(
+                            List::new(|| {
+                                Flex::row()
+                                    .with_flex_child(Label::dynamic(|db: &DatabaseSettings, _| db.display_name()).expand_width(), 1.0)
+                                    .with_spacer(3.0)
+                                    .with_child(
+                                        WidgetButton::new(Icon::new(CLOSE).padding(3.0))
+                                            .on_click(|ctx, db: &mut DatabaseSettings, _| {
+                                                let path = db.path.clone();
+                                                ctx.get_external_handle()
+                                                    .add_idle_callback(move |ui: &mut MainUi| {
+                                                        ui.close_popup();
+                                                        if let AppState::Main(state) = &mut ui.state {
+                                                            state.databases.retain(|db| db.path != path);
+                                                        }
+                                                    })
+                                            })
+                                            .fix_size(30.0, 30.0)
+                                    )
+                            })
+                            .with_spacing(3.0)
+                            .lens(DatabasesPopup::open)
+                        )
+                ))
+                .scroll()
+                .vertical()
+                .expand_height(),
+            1.0
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Other...")
+                        .on_click(|ctx, _, _| {
+                            ctx.close_popup();
+                            ctx.open(SetupState::new());
+                        })
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Cancel")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(250.0, 300.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn conflict_popup() -> impl Widget<String> + 'static {
+    let button = |name: &str, resolution: Resolution| {
+        Button::new(name)
+            .on_click(move |ctx, path: &mut String, _| resolve_conflict(ctx, path.clone(), resolution))
+            .expand_width()
+    };
+    Flex::column()
+        .with_child(
+            Label::new("Conflict")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_flex_child(
+            Label::dynamic(|path: &String, _| format!("{} was changed by another program since it was opened.", path))
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .center(),
+            1.0
+        )
+        .with_spacer(5.0)
+        .with_child(button("Merge", Resolution::Merge))
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(button("Reload", Resolution::Reload), 1.0)
+                .with_spacer(3.0)
+                .with_flex_child(button("Overwrite", Resolution::Overwrite), 1.0)
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Cancel")
+                .on_click(|ctx, _, _| ctx.close_popup())
+                .expand_width()
+        )
+        .padding(6.0)
+        .fix_size(250.0, 200.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn missing_database_popup() -> impl Widget<String> + 'static {
+    Flex::column()
+        .with_child(
+            Label::new("Database not found")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_flex_child(
+            Label::dynamic(|path: &String, _| {
+                format!(
+                    "Could not find the database at {}. If it is on a drive or in a folder that is not available right now, keep it.",
+                    path
+                )
+            })
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .center(),
+            1.0
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Keep")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Forget")
+                        .on_click(|ctx, path: &mut String, _| forget_confirmed(ctx, path.clone()))
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(250.0, 180.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
 fn saving_popup() -> impl Widget<bool> + 'static {
     Flex::column()
         .with_child(Label::new("Saving.."))
@@ -112,6 +494,439 @@ fn leave_popup() -> impl Widget<()> + 'static {
         .rounded(5.0)
 }
 
+fn purge_popup() -> impl Widget<Option<AccountId>> + 'static {
+    Flex::column()
+        .with_flex_child(
+            Label::dynamic(|id: &Option<AccountId>, _| match id {
+                Some(_) => "Permanently delete this account?".to_string(),
+                None => "Permanently delete all accounts in the trash?".to_string()
+            })
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .center(),
+            1.0
+        )
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Delete")
+                        .on_click(|ctx, id: &mut Option<AccountId>, _| purge_confirmed(ctx, *id))
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Cancel")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(200.0, 100.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn tag_popup() -> impl Widget<String> + 'static {
+    let button = |name: &str, add: bool| {
+        Button::new(name)
+            .on_click(move |ctx, tag: &mut String, _| tag_confirmed(ctx, tag.trim().to_owned(), add))
+            .disabled_if(|tag: &String, _| tag.trim().is_empty())
+            .expand_width()
+    };
+    Flex::column()
+        .with_child(
+            Label::new("Tag selected accounts")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_child(TextBox::new().with_placeholder("Tag").expand_width())
+        .with_flex_spacer(1.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(button("Add", true), 1.0)
+                .with_spacer(3.0)
+                .with_flex_child(button("Remove", false), 1.0)
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Cancel")
+                .on_click(|ctx, _, _| ctx.close_popup())
+                .expand_width()
+        )
+        .padding(6.0)
+        .fix_size(220.0, 150.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn move_accounts_popup() -> impl Widget<Vector<DatabaseSettings>> + 'static {
+    Flex::column()
+        .with_child(
+            Label::new("Move selected accounts to")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_flex_child(
+            List::new(|| {
+                Button::dynamic(|db: &DatabaseSettings, _| db.display_name())
+                    .on_click(|ctx, db: &mut DatabaseSettings, _| move_confirmed(ctx, db.path.clone()))
+                    .expand_width()
+            })
+            .with_spacing(3.0)
+            .scroll()
+            .vertical()
+            .expand_height(),
+            1.0
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Button::new("Cancel")
+                .on_click(|ctx, _, _| ctx.close_popup())
+                .expand_width()
+        )
+        .padding(6.0)
+        .fix_size(250.0, 200.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn share_popup() -> impl Widget<SharePopup> + 'static {
+    Flex::column()
+        .with_child(
+            Label::new("Encrypt selected accounts")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_child(Either::new(
+            |popup: &SharePopup, _| popup.is_kit(),
+            SizedBox::empty(),
+            RadioGroup::column([("Passphrase", false), ("age recipients", true)])
+                .lens(SharePopup::recipients)
+                .align_left()
+        ))
+        .with_spacer(5.0)
+        .with_flex_child(
+            Either::new(
+                |popup: &SharePopup, _| popup.uses_recipients(),
+                TextBox::multiline()
+                    .with_placeholder("age1...")
+                    .expand()
+                    .lens(SharePopup::keys),
+                password_field("Passphrase:").lens(SharePopup::passphrase)
+            ),
+            1.0
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Export")
+                        .on_click(|ctx, popup: &mut SharePopup, _| share_confirmed(ctx, popup.path.clone(), popup.protection()))
+                        .disabled_if(|popup: &SharePopup, _| popup.is_empty())
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Cancel")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(280.0, 240.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn unlock_popup() -> impl Widget<UnlockPopup> + 'static {
+    Flex::column()
+        .with_child(
+            Label::new("Encrypted file")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Label::new("Enter the passphrase or, if the file was encrypted for you, your age secret key.")
+                .with_line_break_mode(LineBreaking::WordWrap)
+        )
+        .with_spacer(5.0)
+        .with_child(password_field("Passphrase or key:").lens(UnlockPopup::secret))
+        .with_flex_spacer(1.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Import")
+                        .on_click(|ctx, popup: &mut UnlockPopup, _| unlock_confirmed(ctx, popup.path.clone(), popup.secret.clone()))
+                        .disabled_if(|popup: &UnlockPopup, _| popup.secret.is_empty())
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Cancel")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(280.0, 200.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn change_password_popup() -> impl Widget<ChangePasswordPopup> + 'static {
+    Flex::column()
+        .with_child(
+            Label::new("Change password")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_child(password_field("Current Password:").lens(ChangePasswordPopup::current))
+        .with_spacer(3.0)
+        .with_child(password_field("New Password:").lens(ChangePasswordPopup::password1))
+        .with_child(ComputedWidget::new(strength_meter(), |popup: &ChangePasswordPopup| rate(&popup.password1, "")))
+        .with_spacer(3.0)
+        .with_child(password_field("Repeat Password:").lens(ChangePasswordPopup::password2))
+        .with_spacer(3.0)
+        .with_child(ComputedWidget::new(
+            Maybe::or_empty(|| {
+                Label::dynamic(|msg: &VerificationError, _| msg.to_string())
+                    .with_text_color(RED)
+                    .with_line_break_mode(LineBreaking::WordWrap)
+            }),
+            |popup: &ChangePasswordPopup| check_new_password(&popup.password1, &popup.password2).err()
+        ))
+        .with_flex_spacer(1.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Change")
+                        .on_click(|ctx, popup: &mut ChangePasswordPopup, _| {
+                            change_password_confirmed(ctx, popup.current.clone(), popup.password1.clone())
+                        })
+                        .disabled_if(|popup: &ChangePasswordPopup, _| {
+                            popup.current.is_empty() || check_new_password(&popup.password1, &popup.password2).is_err()
+                        })
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Cancel")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(280.0, 320.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn export_popup() -> impl Widget<ExportPopup> + 'static {
+    Flex::column()
+        .with_child(
+            Label::new("Export accounts")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(Checkbox::new("Include passwords").lens(ExportOptions::passwords))
+                .with_spacer(3.0)
+                .with_child(Checkbox::new("Include notes").lens(ExportOptions::notes))
+                .with_spacer(3.0)
+                .with_child(
+                    Flex::row()
+                        .with_child(Label::dynamic(|minutes: &u32, _| match minutes {
+                            0 => "Delete the file: never".to_string(),
+                            minutes => format!("Delete the file after {} min or on exit", minutes)
+                        }))
+                        .with_flex_spacer(1.0)
+                        .with_child(
+                            Stepper::new()
+                                .with_range(0.0, 120.0)
+                                .with_step(5.0)
+                                .lens(lens::Identity.map(|minutes: &u32| *minutes as f64, |minutes: &mut u32, value: f64| *minutes = value as u32))
+                        )
+                        .expand_width()
+                        .lens(ExportOptions::delete_after)
+                )
+                .lens(ExportPopup::options)
+        )
+        .with_spacer(3.0)
+        .with_child(Either::new(
+            |popup: &ExportPopup, _| popup.selected.is_empty(),
+            SizedBox::empty(),
+            RadioGroup::column([("Selected accounts", true), ("All accounts", false)])
+                .lens(ExportPopup::options.then(ExportOptions::selected_only))
+                .align_left()
+        ))
+        .with_flex_spacer(1.0)
+        .with_child(
+            Label::dynamic(|popup: &ExportPopup, _| {
+                let warning = match popup.options.passwords {
+                    true => "The file is not encrypted. Anyone who can open it can read the passwords.",
+                    false => "The file is not encrypted."
+                };
+                match popup.options.delete_after {
+                    0 => warning.to_string(),
+                    _ => format!("{} Deleting it doesn't guarantee that copies of its content are gone from the disk.", warning)
+                }
+            })
+            .with_text_color(RED)
+            .with_line_break_mode(LineBreaking::WordWrap)
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Export")
+                        .on_click(|ctx, popup: &mut ExportPopup, _| popup.confirmed(ctx))
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Cancel")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(280.0, 280.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+}
+
+fn count_stepper(min: u32, max: u32) -> impl Widget<u32> {
+    Stepper::new()
+        .with_range(min as f64, max as f64)
+        .with_step(1.0)
+        .lens(lens::Identity.map(|count: &u32| *count as f64, |count: &mut u32, value: f64| *count = value as u32))
+}
+
+fn generator_popup() -> impl Widget<GeneratorPopup> {
+    Flex::column()
+        .with_child(
+            Label::new("Generate password")
+                .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_weight(FontWeight::SEMI_BOLD))
+                .with_text_size(15.0)
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Label::dynamic(|popup: &GeneratorPopup, _| match &popup.password {
+                        Ok(password) => password.expose().to_string(),
+                        Err(_) => String::new()
+                    })
+                        .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
+                        .with_line_break_mode(LineBreaking::WordWrap)
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_child(Button::new("New").on_click(|_, popup: &mut GeneratorPopup, _| popup.regenerate()))
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Label::dynamic(
+                |popup: &GeneratorPopup, _| match &popup.password {
+                    Ok(_) => "Follows Riot's password rules".to_string(),
+                    Err(violation) => violation.to_string()
+                }
+            )
+            .with_text_size(TEXT_SIZE_SMALL)
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .align_left()
+        )
+        .with_spacer(5.0)
+        .with_child(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_child(
+                    RadioGroup::column([
+                        ("Random characters", GeneratorMode::Random),
+                        ("Pronounceable", GeneratorMode::Pronounceable),
+                        ("Passphrase", GeneratorMode::Passphrase)
+                    ])
+                    .lens(GeneratorSettings::mode)
+                )
+                .with_spacer(3.0)
+                .with_child(Either::new(
+                    |options: &GeneratorSettings, _| options.mode == GeneratorMode::Passphrase,
+                    Flex::row()
+                        .with_child(Label::dynamic(|words: &u32, _| format!("Words: {}", words)))
+                        .with_flex_spacer(1.0)
+                        .with_child(count_stepper(MIN_WORDS, MAX_WORDS))
+                        .expand_width()
+                        .lens(GeneratorSettings::words),
+                    Flex::row()
+                        .with_child(Label::dynamic(|length: &u32, _| format!("Length: {}", length)))
+                        .with_flex_spacer(1.0)
+                        .with_child(count_stepper(MIN_LENGTH, MAX_LENGTH))
+                        .expand_width()
+                        .lens(GeneratorSettings::length)
+                ))
+                .with_spacer(3.0)
+                .with_child(
+                    Checkbox::new("Lowercase letters")
+                        .lens(GeneratorSettings::lowercase)
+                        .disabled_if(|options: &GeneratorSettings, _| options.mode != GeneratorMode::Random)
+                )
+                .with_spacer(3.0)
+                .with_child(Checkbox::new("Uppercase letters").lens(GeneratorSettings::uppercase))
+                .with_spacer(3.0)
+                .with_child(Checkbox::new("Digits").lens(GeneratorSettings::digits))
+                .with_spacer(3.0)
+                .with_child(Checkbox::new("Symbols").lens(GeneratorSettings::symbols))
+                .with_spacer(3.0)
+                .with_child(Checkbox::new(format!("Exclude ambiguous characters ({})", AMBIGUOUS)).lens(GeneratorSettings::exclude_ambiguous))
+                .lens(GeneratorPopup::options)
+        )
+        .with_flex_spacer(1.0)
+        .with_child(
+            Flex::row()
+                .with_flex_child(
+                    Button::new("Use")
+                        .on_click(|ctx, popup: &mut GeneratorPopup, _| {
+                            if let Ok(password) = &popup.password {
+                                generator_confirmed(ctx, password.clone(), popup.options.clone())
+                            }
+                        })
+                        .disabled_if(|popup: &GeneratorPopup, _| !popup.options.is_valid() || popup.password.is_err())
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Cancel")
+                        .on_click(|ctx, _, _| ctx.close_popup())
+                        .expand_width(),
+                    1.0
+                )
+        )
+        .padding(6.0)
+        .fix_size(300.0, 400.0)
+        .background(BACKGROUND_DARK)
+        .rounded(5.0)
+        .controller(Regenerate)
+}
+
 struct DelayClose;
 
 impl<W: Widget<bool>> Controller<bool, W> for DelayClose {
@@ -124,3 +939,16 @@ impl<W: Widget<bool>> Controller<bool, W> for DelayClose {
         child.event(ctx, event, data, env)
     }
 }
+
+/// Generates a new password whenever the options of the generator change.
+struct Regenerate;
+
+impl<W: Widget<GeneratorPopup>> Controller<GeneratorPopup, W> for Regenerate {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut GeneratorPopup, env: &Env) {
+        let options = data.options.clone();
+        child.event(ctx, event, data, env);
+        if options != data.options && data.options.is_valid() {
+            data.regenerate();
+        }
+    }
+}
@@ -1,57 +1,69 @@
-use std::ffi::OsStr;
-use std::fs::File;
-use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::thread::spawn;
 
 use anyhow::anyhow;
-use druid::commands::{SAVE_FILE_AS, SHOW_SAVE_PANEL};
+use druid::commands::{OPEN_FILE, SAVE_FILE_AS, SHOW_OPEN_PANEL, SHOW_SAVE_PANEL};
 use druid::theme::{BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
-use druid::widget::{Button, Checkbox, Controller, CrossAxisAlignment, Flex, Label, LineBreaking, RadioGroup};
-use druid::{Data, Env, Event, EventCtx, FileDialogOptions, FileSpec, Lens, Widget, WidgetExt};
+use druid::im::Vector;
+use druid::widget::{Button, Checkbox, Controller, CrossAxisAlignment, Either, Flex, Label, LineBreaking, List, RadioGroup, Stepper, TextBox};
+use druid::{lens, Data, Env, Event, EventCtx, FileDialogOptions, Lens, LensExt, Selector, Widget, WidgetExt};
+use druid_material_icons::normal::action::DELETE;
 
-use crate::data::{Settings, Theme};
+use crate::data::{self, Settings, Theme};
+use crate::export::{self, AGE_YAML, YAML};
 use crate::screens::main::MainState;
+use crate::screens::merge_import::MergeImportState;
+use crate::screens::popup::PopupState;
 use crate::screens::setup::SetupState;
 use crate::screens::{AppState, MainUi, Navigator};
+use crate::store::SecretStoreKind;
+use crate::util::SecretString;
+use crate::widgets::{Icon, WidgetButton};
+use crate::{import, kit};
 
-const YAML: FileSpec = FileSpec::new("yaml file", &["yml", "yaml"]);
-const TXT: FileSpec = FileSpec::new("text file", &["txt"]);
+const FORGET_PASSWORD: Selector<String> = Selector::new("lol_account_manager_v2.settings.forget");
 
 #[derive(Clone, Data, Lens)]
 pub struct SettingsState {
     pub previous: MainState,
-    pub settings: Settings
+    pub settings: Settings,
+    saved_passwords: Vector<String>
 }
 
 impl SettingsState {
-    fn save(&self, ctx: &EventCtx) {
-        let settings = self.settings.clone();
-        ctx.get_external_handle()
-            .add_idle_callback(move |ui: &mut MainUi| match settings.save() {
-                Ok(()) => ui.settings = settings,
-                Err(err) => ui.open_popup(err.into())
-            })
+    pub fn new(previous: MainState, settings: Settings) -> Self {
+        let saved_passwords = settings.saved_passwords();
+        Self {
+            previous,
+            settings,
+            saved_passwords
+        }
     }
 
-    fn export_txt(&self, path: &Path) -> anyhow::Result<()> {
-        let file = File::create(path)?;
-        let mut writer = BufWriter::new(file);
-        for account in &self.previous.database.accounts {
-            writeln!(writer, "Name: {}", account.name)?;
-            writeln!(writer, "Username: {}", account.username)?;
-            writeln!(writer, "Password: {}", account.password)?;
-            writeln!(writer, "Notes:\n{}", account.notes)?;
-            writeln!(writer)?;
-        }
-        writer.flush()?;
+    fn forget_password(&mut self, path: &str) -> anyhow::Result<()> {
+        self.settings.secret_store.get().delete(path)?;
+        self.settings.set_remember_password(path, false);
+        self.saved_passwords.retain(|saved| saved != path);
         Ok(())
     }
 
-    fn export_yml(&self, path: &Path) -> anyhow::Result<()> {
-        let file = File::create(path)?;
-        let writer = BufWriter::new(file);
-        serde_yaml::to_writer(writer, &self.previous.database.accounts)?;
-        Ok(())
+    fn save(&self, ctx: &EventCtx) {
+        let settings = self.settings.clone();
+        let path = self.previous.primary().path.clone();
+        let password = self.previous.primary().password.clone();
+        ctx.get_external_handle()
+            .add_idle_callback(move |ui: &mut MainUi| {
+                if let Err(err) = settings.move_passwords(&ui.settings, &path, &password) {
+                    ui.open_popup(err.into());
+                }
+                if let AppState::Settings(state) = &mut ui.state {
+                    state.saved_passwords = settings.saved_passwords();
+                }
+                match settings.save() {
+                    Ok(()) => ui.settings = settings,
+                    Err(err) => ui.open_popup(err.into())
+                }
+            })
     }
 
     pub fn widget() -> impl Widget<Self> + 'static {
@@ -67,18 +79,34 @@ impl From<SettingsState> for AppState {
 
 fn build_settings_ui() -> impl Widget<SettingsState> {
     Flex::column()
-        .with_child(
+        .with_flex_child(
             Flex::column()
-                .with_child(after_login_ui().lens(Settings::close_on_login))
+                .with_child(
+                    Flex::column()
+                        .with_child(after_login_ui().lens(Settings::close_on_login))
+                        .with_spacer(3.0)
+                        .with_child(theme_ui().lens(Settings::theme))
+                        .with_spacer(3.0)
+                        .with_child(lock_ui())
+                        .with_spacer(3.0)
+                        .with_child(trash_ui().lens(Settings::trash_retention))
+                        .with_spacer(3.0)
+                        .with_child(secret_store_ui().lens(Settings::secret_store))
+                        .lens(SettingsState::settings)
+                        .controller(StoreSwitcher)
+                )
                 .with_spacer(3.0)
-                .with_child(theme_ui().lens(Settings::theme))
-                .lens(SettingsState::settings)
+                .with_child(database_ui())
+                .with_spacer(3.0)
+                .with_child(saved_passwords_ui())
+                .with_spacer(3.0)
+                .with_child(info_ui())
+                .scroll()
+                .vertical()
+                .expand_height(),
+            1.0
         )
         .with_spacer(3.0)
-        .with_child(database_ui())
-        .with_spacer(3.0)
-        .with_child(info_ui())
-        .with_flex_spacer(1.0)
         .with_child(
             Button::new("Back")
                 .on_click(|ctx, state: &mut SettingsState, _| {
@@ -116,6 +144,79 @@ fn theme_ui() -> impl Widget<Theme> {
         .rounded(TEXTBOX_BORDER_RADIUS)
 }
 
+fn secret_store_ui() -> impl Widget<SecretStoreKind> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Password Storage:"))
+        .with_spacer(6.0)
+        .with_child(
+            RadioGroup::column([
+                ("System keyring", SecretStoreKind::Keyring),
+                ("Until the program is closed", SecretStoreKind::Memory),
+                ("Don't store passwords", SecretStoreKind::Disabled)
+            ])
+            .padding((6.0, 0.0))
+        )
+        .padding(5.0)
+        .expand_width()
+        .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
+fn lock_ui() -> impl Widget<Settings> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Auto Lock:"))
+        .with_spacer(3.0)
+        .with_child(Checkbox::new("Lock when minimized").lens(Settings::lock_on_minimize))
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .with_child(Label::dynamic(|minutes: &u32, _| match minutes {
+                    0 => "Lock after inactivity: never".to_string(),
+                    minutes => format!("Lock after inactivity: {} min", minutes)
+                }))
+                .with_flex_spacer(1.0)
+                .with_child(Stepper::new().with_range(0.0, 120.0).with_step(5.0).lens(lens::Identity.map(
+                    |minutes: &u32| *minutes as f64,
+                    |minutes: &mut u32, value: f64| *minutes = value as u32
+                )))
+                .expand_width()
+                .lens(Settings::lock_timeout)
+        )
+        .padding(5.0)
+        .expand_width()
+        .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
+fn trash_ui() -> impl Widget<u32> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Trash:"))
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .with_child(Label::dynamic(|days: &u32, _| match days {
+                    0 => "Keep deleted accounts: until emptied".to_string(),
+                    1 => "Keep deleted accounts: 1 day".to_string(),
+                    days => format!("Keep deleted accounts: {} days", days)
+                }))
+                .with_flex_spacer(1.0)
+                .with_child(
+                    Stepper::new()
+                        .with_range(0.0, 365.0)
+                        .with_step(1.0)
+                        .lens(lens::Identity.map(|days: &u32| *days as f64, |days: &mut u32, value: f64| *days = value as u32))
+                )
+                .expand_width()
+        )
+        .padding(5.0)
+        .expand_width()
+        .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
 fn database_ui() -> impl Widget<SettingsState> {
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
@@ -135,25 +236,123 @@ fn database_ui() -> impl Widget<SettingsState> {
                 .with_spacer(3.0)
                 .with_flex_child(
                     Button::new("Export")
-                        .on_click(|ctx, _, _| {
-                            ctx.submit_command(
-                                SHOW_SAVE_PANEL.with(
-                                    FileDialogOptions::new()
-                                        .allowed_types(vec![YAML, TXT])
-                                        .default_name("accounts")
-                                        .default_type(TXT)
-                                )
-                            )
-                        })
+                        .on_click(|ctx, _, _| ctx.submit_command(SHOW_SAVE_PANEL.with(export::dialog_options())))
+                        .expand_width(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    Button::new("Emergency kit")
+                        .on_click(|ctx, _, _| ctx.submit_command(SHOW_SAVE_PANEL.with(kit::dialog_options())))
                         .expand_width(),
                     1.0
                 )
         )
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Import into current database")
+                .on_click(|ctx, _, _| {
+                    let mut formats = vec![YAML, AGE_YAML];
+                    formats.extend(import::FORMATS);
+                    ctx.submit_command(SHOW_OPEN_PANEL.with(FileDialogOptions::new().allowed_types(formats)))
+                })
+                .expand_width()
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .with_child(Label::new("Label:"))
+                .with_spacer(3.0)
+                .with_flex_child(
+                    TextBox::new()
+                        .with_placeholder("Name in the recent list")
+                        .expand_width()
+                        .lens(lens::Identity.map(
+                            |state: &SettingsState| {
+                                state
+                                    .settings
+                                    .database(&state.previous.primary().path)
+                                    .map(|db| db.label.clone())
+                                    .unwrap_or_default()
+                            },
+                            |state: &mut SettingsState, label: String| {
+                                let path = &state.previous.primary().path;
+                                if let Some(db) = state.settings.databases.iter_mut().find(|db| db.path == *path) {
+                                    if db.label != label {
+                                        db.label = label;
+                                    }
+                                }
+                            }
+                        )),
+                    1.0
+                )
+        )
+        .with_spacer(3.0)
+        .with_child(Checkbox::new("Remember the password on this computer").lens(lens::Identity.map(
+            |state: &SettingsState| state.settings.remember_password(&state.previous.primary().path),
+            |state: &mut SettingsState, remember: bool| {
+                if state.settings.remember_password(&state.previous.primary().path) != remember {
+                    let path = state.previous.primary().path.clone();
+                    state.settings.set_remember_password(&path, remember);
+                }
+            }
+        )))
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Change password")
+                .on_click(|ctx, _, _| ctx.open_popup(PopupState::change_password()))
+                .expand_width()
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Button::new("Forget saved password")
+                .on_click(|ctx, state: &mut SettingsState, _| {
+                    ctx.submit_command(FORGET_PASSWORD.with(state.previous.primary().path.clone()))
+                })
+                .disabled_if(|state: &SettingsState, _| !state.saved_passwords.contains(&state.previous.primary().path))
+                .expand_width()
+        )
         .padding(5.0)
         .expand_width()
         .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
         .rounded(TEXTBOX_BORDER_RADIUS)
         .controller(Exporter)
+        .controller(Importer)
+}
+
+fn saved_passwords_ui() -> impl Widget<SettingsState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Saved Passwords:"))
+        .with_spacer(6.0)
+        .with_child(Either::new(
+            |state: &SettingsState, _| state.saved_passwords.is_empty(),
+            Label::new("There are no saved passwords").padding((6.0, 0.0)),
+            List::new(saved_password_item)
+                .with_spacing(3.0)
+                .lens(SettingsState::saved_passwords)
+        ))
+        .padding(5.0)
+        .expand_width()
+        .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+        .controller(PasswordForgetter)
+}
+
+fn saved_password_item() -> impl Widget<String> {
+    Flex::row()
+        .with_flex_child(
+            Label::dynamic(|path: &String, _| path.clone())
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .expand_width(),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(
+            WidgetButton::new(Icon::new(DELETE).padding(3.0))
+                .on_click(|ctx, path: &mut String, _| ctx.submit_command(FORGET_PASSWORD.with(path.clone())))
+        )
+        .padding((6.0, 0.0))
 }
 
 fn info_ui() -> impl Widget<SettingsState> {
@@ -171,7 +370,7 @@ fn info_ui() -> impl Widget<SettingsState> {
                         .cross_axis_alignment(CrossAxisAlignment::Start)
                         .with_child(Label::new("Database: "))
                         .with_flex_child(
-                            Label::dynamic(|state: &SettingsState, _| state.previous.database.path.clone())
+                            Label::dynamic(|state: &SettingsState, _| state.previous.primary().path.clone())
                                 .with_line_break_mode(LineBreaking::WordWrap),
                             1.0
                         )
@@ -184,27 +383,136 @@ fn info_ui() -> impl Widget<SettingsState> {
         .rounded(TEXTBOX_BORDER_RADIUS)
 }
 
+/// Called by the popup that asks for a new password for the primary database. The database is saved right away, and a
+/// saved password is replaced as well.
+pub fn change_password_confirmed(ctx: &EventCtx, current: SecretString, password: SecretString) {
+    let handle = ctx.get_external_handle();
+    handle
+        .clone()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            let mut db = match &ui.state {
+                AppState::Settings(state) => state.previous.primary().clone(),
+                _ => return ui.close_popup()
+            };
+            if db.password != current {
+                return ui.open_popup(anyhow!("The current password is wrong").into());
+            }
+            let store = ui.settings.password_store(&db.path);
+            ui.open_popup(PopupState::saving());
+            spawn(move || {
+                let result = match db.changed_on_disk() {
+                    Ok(true) => Err(anyhow!("The file was changed by another program. Reopen the database and try again.")),
+                    Ok(false) => {
+                        db.password = password;
+                        db.save()
+                    }
+                    Err(err) => Err(err)
+                };
+                if let Err(err) = result {
+                    return handle.open_popup(err.into());
+                }
+                if let Some(store) = store {
+                    if let Err(err) = store.get().store(&db.path, &db.password) {
+                        return handle.open_popup(err.into());
+                    }
+                }
+                handle.add_idle_callback(move |ui: &mut MainUi| {
+                    ui.close_popup();
+                    if let AppState::Settings(state) = &mut ui.state {
+                        if state.previous.primary().path == db.path {
+                            state.previous.databases[0] = db;
+                        }
+                    }
+                });
+            });
+        })
+}
+
+/// Called by the popup that asks for the passphrase or key of an encrypted file that should be merged into the database.
+pub fn unlock_confirmed(ctx: &EventCtx, path: String, secret: SecretString) {
+    ctx.get_external_handle()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            ui.close_popup();
+            let result = match &ui.state {
+                AppState::Settings(state) => {
+                    data::read_shared(&path, &secret).map(|accounts| MergeImportState::new(state.clone(), Path::new(&path), accounts))
+                }
+                _ => return
+            };
+            match result {
+                Ok(screen) => ui.open(screen),
+                Err(err) => ui.open_popup(err.into())
+            }
+        })
+}
+
 struct Exporter;
 
 impl<W: Widget<SettingsState>> Controller<SettingsState, W> for Exporter {
     fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut SettingsState, env: &Env) {
         if let Event::Command(cmd) = event {
             if let Some(file) = cmd.get(SAVE_FILE_AS) {
-                let spec = file
-                    .path
-                    .extension()
-                    .and_then(OsStr::to_str)
-                    .and_then(|ext| {
-                        [TXT, YAML]
-                            .into_iter()
-                            .find(|spec| spec.extensions.contains(&ext))
-                    });
-                match spec {
-                    Some(TXT) => data.export_txt(&file.path),
-                    Some(YAML) => data.export_yml(&file.path),
-                    _ => Err(anyhow!("Unknown Format"))
+                match kit::is_kit(&file.path) {
+                    // The database on disk already is encrypted, so it can be printed as it is.
+                    true => std::fs::read(&data.previous.primary().path)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|content| kit::write(&file.path, &data.previous.primary().name(), &content))
+                        .unwrap_or_else(|err| ctx.open_popup(err.into())),
+                    false => ctx.open_popup(PopupState::export(
+                        file.path.to_string_lossy().into_owned(),
+                        data.previous.primary().accounts.clone(),
+                        Vector::new()
+                    ))
                 }
-                .unwrap_or_else(|err| ctx.open_popup(err.into()))
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
+
+/// Saves the settings as soon as a different password storage is picked, so the saved passwords are moved right away
+/// and the list below shows the new store.
+struct StoreSwitcher;
+
+impl<W: Widget<SettingsState>> Controller<SettingsState, W> for StoreSwitcher {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut SettingsState, env: &Env) {
+        let store = data.settings.secret_store;
+        child.event(ctx, event, data, env);
+        if store != data.settings.secret_store {
+            data.save(ctx);
+        }
+    }
+}
+
+struct Importer;
+
+impl<W: Widget<SettingsState>> Controller<SettingsState, W> for Importer {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut SettingsState, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some(file) = cmd.get(OPEN_FILE) {
+                data.save(ctx);
+                let path = file.path.to_string_lossy().into_owned();
+                match export::is_encrypted(&file.path) {
+                    true => ctx.open_popup(PopupState::unlock(path)),
+                    false => match import::read_accounts(&path) {
+                        Ok(accounts) => ctx.open(MergeImportState::new(data.clone(), &file.path, accounts)),
+                        Err(err) => ctx.open_popup(err.into())
+                    }
+                }
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
+
+struct PasswordForgetter;
+
+impl<W: Widget<SettingsState>> Controller<SettingsState, W> for PasswordForgetter {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut SettingsState, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some(path) = cmd.get(FORGET_PASSWORD) {
+                data.forget_password(path)
+                    .unwrap_or_else(|err| ctx.open_popup(err.into()))
             }
         }
         child.event(ctx, event, data, env)
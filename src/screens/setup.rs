@@ -1,24 +1,34 @@
 use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
 
+use anyhow::anyhow;
+use druid::im::Vector;
 use druid::theme::{BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
-use druid::widget::{Button, CrossAxisAlignment, Flex, Label, Maybe, RadioGroup};
-use druid::{Data, FileDialogOptions, FileSpec, Lens, Widget, WidgetExt};
+use druid::widget::{Button, Checkbox, Controller, CrossAxisAlignment, Either, Flex, Label, List, Maybe, RadioGroup, SizedBox, TextBox};
+use druid::{Data, Env, EventCtx, FileDialogOptions, Lens, LifeCycle, LifeCycleCtx, Widget, WidgetExt};
 use druid_widget_nursery::enum_switcher::Switcher;
 use druid_widget_nursery::prism::Prism;
 use druid_widget_nursery::ComputedWidget;
 
-use crate::data::{Database, Password};
+use crate::data::{Account, Database, DatabaseSettings, Settings};
+use crate::export::{AGE_YAML, YAML};
+use crate::generator::{check_riot_rules, RuleViolation};
+use crate::import::{self, Imported};
+use crate::kit;
+use crate::screens::import::ImportPreviewState;
 use crate::screens::main::MainState;
+use crate::screens::start::StartupState;
 use crate::screens::{AppState, MainUi, Navigator};
-use crate::util::{password_field, path_field, PathOptions};
-
-const YAML: FileSpec = FileSpec::new("yaml file", &["yml", "yaml"]);
-
-const AGE_YAML: FileSpec = FileSpec::new("encrypted yaml file", &["yml.age", "yaml.age"]);
+use crate::strength::{estimate, rate, Strength};
+use crate::util::{password_field, path_field, strength_meter, PathOptions, SecretString};
 
 #[derive(Clone, Data, Lens)]
 pub struct SetupState {
-    state: ActionState
+    state: ActionState,
+    remember_password: bool,
+    recent: Vector<DatabaseSettings>
 }
 
 impl SetupState {
@@ -36,16 +46,58 @@ impl From<SetupState> for AppState {
 impl SetupState {
     pub fn new() -> Self {
         Self {
-            state: ActionState::Create(Default::default())
+            state: ActionState::Create(Default::default()),
+            remember_password: true,
+            recent: Vector::new()
+        }
+    }
+
+    /// Reloads the recently used databases after they were changed elsewhere.
+    pub fn reload_recent(&mut self, settings: &Settings) {
+        self.recent = settings.databases.clone();
+    }
+
+    /// Creates the database that is imported into, once the accounts have been read from a file that needs further input.
+    pub fn import(&self, accounts: Vector<Account>) -> anyhow::Result<Database> {
+        match &self.state {
+            ActionState::Import(state) => Database::create(&state.output_path, &state.password1, accounts),
+            _ => Err(anyhow!("No import is in progress"))
         }
     }
+
+    /// Remembers the new database and switches to it.
+    pub fn finish(&self, ctx: &EventCtx, db: Database) {
+        let path = db.path.clone();
+        let password = db.password.clone();
+        let remember = self.remember_password;
+        ctx.get_external_handle()
+            .add_idle_callback(move |ui: &mut MainUi| {
+                let store = ui.settings.secret_store.get();
+                let stored = match remember {
+                    true => store.store(&path, &password),
+                    false => store.delete(&path)
+                };
+                if let Err(err) = stored {
+                    ui.open_popup(err.into())
+                }
+                let saved = ui.settings.open_database(&path).and_then(|_| {
+                    ui.settings.set_remember_password(&path, remember);
+                    ui.settings.save()
+                });
+                if let Err(err) = saved {
+                    ui.open_popup(err.into())
+                }
+            });
+        ctx.open(MainState::new(Vector::unit(db)));
+    }
 }
 
 #[derive(Clone, Data, Prism)]
 enum ActionState {
     Create(CreateState),
     Open(OpenState),
-    Import(ImportState)
+    Import(ImportState),
+    Restore(RestoreState)
 }
 
 impl PartialEq for ActionState {
@@ -57,8 +109,8 @@ impl PartialEq for ActionState {
 #[derive(Clone, Data, Default, Eq, PartialEq, Lens)]
 struct CreateState {
     path: String,
-    password1: String,
-    password2: String
+    password1: SecretString,
+    password2: SecretString
 }
 
 fn build_create_ui() -> impl Widget<CreateState> {
@@ -69,6 +121,7 @@ fn build_create_ui() -> impl Widget<CreateState> {
         .with_child(path_field("Destination:", PathOptions::Save(options)).lens(CreateState::path))
         .with_spacer(3.0)
         .with_child(password_field("Password:").lens(CreateState::password1))
+        .with_child(ComputedWidget::new(strength_meter(), |state: &CreateState| rate(&state.password1, "")))
         .with_spacer(3.0)
         .with_child(password_field("Repeat Password:").lens(CreateState::password2))
 }
@@ -76,7 +129,7 @@ fn build_create_ui() -> impl Widget<CreateState> {
 #[derive(Clone, Data, Default, Eq, PartialEq, Lens)]
 struct OpenState {
     path: String,
-    password: String
+    password: SecretString
 }
 
 fn build_open_ui() -> impl Widget<OpenState> {
@@ -93,13 +146,15 @@ fn build_open_ui() -> impl Widget<OpenState> {
 struct ImportState {
     input_path: String,
     output_path: String,
-    password1: String,
-    password2: String
+    password1: SecretString,
+    password2: SecretString
 }
 
 fn build_import_ui() -> impl Widget<ImportState> {
+    let mut formats = vec![YAML];
+    formats.extend(import::FORMATS);
     let source = FileDialogOptions::new()
-        .allowed_types(vec![YAML])
+        .allowed_types(formats)
         .default_name("database.yml");
     let destination = FileDialogOptions::new()
         .allowed_types(vec![AGE_YAML])
@@ -110,12 +165,65 @@ fn build_import_ui() -> impl Widget<ImportState> {
         .with_child(path_field("Path:", PathOptions::Save(destination)).lens(ImportState::output_path))
         .with_spacer(3.0)
         .with_child(password_field("Password:").lens(ImportState::password1))
+        .with_child(ComputedWidget::new(strength_meter(), |state: &ImportState| rate(&state.password1, "")))
         .with_spacer(3.0)
         .with_child(password_field("Repeat Password:").lens(ImportState::password2))
 }
 
+#[derive(Clone, Data, Default, Eq, PartialEq, Lens)]
+struct RestoreState {
+    /// The text of the scanned QR codes of an emergency kit.
+    codes: String,
+    path: String,
+    password: SecretString
+}
+
+fn build_restore_ui() -> impl Widget<RestoreState> {
+    let options = FileDialogOptions::new()
+        .allowed_types(vec![AGE_YAML])
+        .default_name("database.yml.age");
+    Flex::column()
+        .with_child(
+            TextBox::multiline()
+                .with_placeholder("Text of the scanned codes")
+                .expand_width()
+                .fix_height(100.0)
+                .lens(RestoreState::codes)
+        )
+        .with_spacer(3.0)
+        .with_child(path_field("Destination:", PathOptions::Save(options)).lens(RestoreState::path))
+        .with_spacer(3.0)
+        .with_child(password_field("Password:").lens(RestoreState::password))
+}
+
+fn build_recent_ui() -> impl Widget<SetupState> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(Label::new("Recent:"))
+        .with_spacer(6.0)
+        .with_child(
+            List::new(|| {
+                Button::dynamic(|db: &DatabaseSettings, _| db.display_name())
+                    .on_click(|ctx, db: &mut DatabaseSettings, _| ctx.open(StartupState::open(db.path.clone())))
+                    .expand_width()
+            })
+            .with_spacing(3.0)
+            .lens(SetupState::recent)
+        )
+        .padding(3.0)
+        .expand_width()
+        .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+        .rounded(TEXTBOX_BORDER_RADIUS)
+        .padding((0.0, 0.0, 0.0, 3.0))
+}
+
 fn build_setup_ui() -> impl Widget<SetupState> {
     Flex::column()
+        .with_child(Either::new(
+            |state: &SetupState, _| state.recent.is_empty(),
+            SizedBox::empty(),
+            build_recent_ui()
+        ))
         .with_child(
             Flex::column()
                 .cross_axis_alignment(CrossAxisAlignment::Start)
@@ -125,7 +233,8 @@ fn build_setup_ui() -> impl Widget<SetupState> {
                     RadioGroup::column([
                         ("Create a new database", ActionState::Create(Default::default())),
                         ("Open an exising database", ActionState::Open(Default::default())),
-                        ("Import an unencrypted database", ActionState::Import(Default::default()))
+                        ("Import an unencrypted database", ActionState::Import(Default::default())),
+                        ("Restore an emergency kit", ActionState::Restore(Default::default()))
                     ])
                     .padding((6.0, 0.0))
                 )
@@ -141,9 +250,16 @@ fn build_setup_ui() -> impl Widget<SetupState> {
                 .with_variant(ActionStateCreate, build_create_ui())
                 .with_variant(ActionStateOpen, build_open_ui())
                 .with_variant(ActionStateImport, build_import_ui())
+                .with_variant(ActionStateRestore, build_restore_ui())
                 .lens(SetupState::state)
         )
         .with_flex_spacer(1.0)
+        .with_child(
+            Checkbox::new("Remember the password on this computer")
+                .lens(SetupState::remember_password)
+                .align_left()
+        )
+        .with_spacer(3.0)
         .with_child(
             ComputedWidget::new(
                 Maybe::or_empty(|| Label::dynamic(|msg: &VerificationError, _| msg.to_string())),
@@ -156,23 +272,19 @@ fn build_setup_ui() -> impl Widget<SetupState> {
             Button::new("Confirm")
                 .expand_width()
                 .fix_height(50.0)
-                .on_click(|ctx, state: &mut SetupState, _| {
-                    let db = Database::try_from(state.state.clone());
-                    match db {
-                        Ok(db) => {
-                            let path = db.path.clone();
-                            ctx.get_external_handle()
-                                .add_idle_callback(|ui: &mut MainUi| {
-                                    ui.settings.last_database = Some(path);
-                                    if let Err(err) = ui.settings.save() {
-                                        ui.open_popup(err.into())
-                                    }
-                                });
-                            if let Err(err) = Password::store(&db.path, &db.password) {
-                                ctx.open_popup(anyhow::Error::from(err).into())
-                            }
-                            ctx.open(MainState::new(db));
-                        }
+                .on_click(|ctx, state: &mut SetupState, _| match &state.state {
+                    // Files that were not written by this program might need some further input before they can be imported.
+                    ActionState::Import(import) if !import::is_native(&import.input_path) => match import::read(&import.input_path) {
+                        Ok(Imported::Accounts(accounts)) => match state.import(accounts) {
+                            Ok(db) => state.finish(ctx, db),
+                            Err(err) => ctx.open_popup(err.into())
+                        },
+                        Ok(Imported::Table(table)) => ctx.open(ImportPreviewState::table(state.clone(), table)),
+                        Ok(Imported::Entries(entries)) => ctx.open(ImportPreviewState::entries(state.clone(), entries)),
+                        Err(err) => ctx.open_popup(err.into())
+                    },
+                    _ => match Database::try_from(state.state.clone()) {
+                        Ok(db) => state.finish(ctx, db),
                         Err(err) => ctx.open_popup(err.into())
                     }
                 })
@@ -180,6 +292,23 @@ fn build_setup_ui() -> impl Widget<SetupState> {
         )
         .padding(6.0)
         .expand()
+        .controller(LoadRecent)
+}
+
+struct LoadRecent;
+
+impl<W: Widget<SetupState>> Controller<SetupState, W> for LoadRecent {
+    fn lifecycle(&mut self, child: &mut W, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &SetupState, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.get_external_handle()
+                .add_idle_callback(|ui: &mut MainUi| {
+                    if let AppState::Setup(state) = &mut ui.state {
+                        state.recent = ui.settings.databases.clone();
+                    }
+                });
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
 }
 
 impl TryFrom<ActionState> for Database {
@@ -189,16 +318,34 @@ impl TryFrom<ActionState> for Database {
         match state {
             ActionState::Create(state) => Database::new(&state.path, &state.password1),
             ActionState::Open(state) => Database::load(&state.path, &state.password),
-            ActionState::Import(state) => Database::import(&state.input_path, &state.output_path, &state.password1)
+            ActionState::Import(state) => Database::import(&state.input_path, &state.output_path, &state.password1),
+            ActionState::Restore(state) => {
+                let content = kit::restore(&state.codes)?;
+                // create_new refuses to replace a file that was created after the path was checked.
+                File::options()
+                    .write(true)
+                    .create_new(true)
+                    .open(&state.path)?
+                    .write_all(&content)?;
+                // A wrong password must not leave the file behind, otherwise the next attempt fails with PathExists.
+                Database::load(&state.path, &state.password).map_err(|err| {
+                    let _ = std::fs::remove_file(&state.path);
+                    err
+                })
+            }
         }
     }
 }
 
 #[derive(Debug, Copy, Clone, Data, Eq, PartialEq)]
-enum VerificationError {
+pub enum VerificationError {
     EmptyPath,
     EmptyPassword,
-    MismatchedPasswords
+    MismatchedPasswords,
+    NoCodes,
+    PathExists,
+    WeakPassword,
+    PolicyViolation(RuleViolation)
 }
 
 impl Display for VerificationError {
@@ -206,7 +353,11 @@ impl Display for VerificationError {
         match self {
             VerificationError::EmptyPath => f.write_str("Paths can't be empty!"),
             VerificationError::EmptyPassword => f.write_str("Passwords can't be empty!"),
-            VerificationError::MismatchedPasswords => f.write_str("The passwords don't match!")
+            VerificationError::MismatchedPasswords => f.write_str("The passwords don't match!"),
+            VerificationError::NoCodes => f.write_str("The codes of the kit are missing!"),
+            VerificationError::PathExists => f.write_str("The destination already exists!"),
+            VerificationError::WeakPassword => f.write_str("The password is too easy to guess!"),
+            VerificationError::PolicyViolation(violation) => write!(f, "{}!", violation)
         }
     }
 }
@@ -216,9 +367,7 @@ impl ActionState {
         match self {
             ActionState::Create(state) => {
                 check_path(&state.path)?;
-                check_password(&state.password1)?;
-                check_passwords(&state.password1, &state.password2)?;
-                Ok(())
+                check_new_password(&state.password1, &state.password2)
             }
             ActionState::Open(state) => {
                 check_path(&state.path)?;
@@ -228,8 +377,17 @@ impl ActionState {
             ActionState::Import(state) => {
                 check_path(&state.input_path)?;
                 check_path(&state.output_path)?;
-                check_password(&state.password1)?;
-                check_passwords(&state.password1, &state.password2)?;
+                check_new_password(&state.password1, &state.password2)
+            }
+            ActionState::Restore(state) => {
+                if state.codes.trim().is_empty() {
+                    return Err(VerificationError::NoCodes);
+                }
+                check_path(&state.path)?;
+                if Path::new(&state.path).exists() {
+                    return Err(VerificationError::PathExists);
+                }
+                check_password(&state.password)?;
                 Ok(())
             }
         }
@@ -243,16 +401,48 @@ fn check_path(path: &str) -> Result<(), VerificationError> {
     Ok(())
 }
 
-fn check_password(password: &str) -> Result<(), VerificationError> {
+fn check_password(password: &SecretString) -> Result<(), VerificationError> {
     if password.is_empty() {
         return Err(VerificationError::EmptyPassword);
     }
     Ok(())
 }
 
-fn check_passwords(password1: &str, password2: &str) -> Result<(), VerificationError> {
+/// The master password protects every account in the database, so it has to follow at least the rules Riot sets
+/// for each of them.
+fn check_strength(password: &SecretString) -> Result<(), VerificationError> {
+    check_riot_rules(password.expose(), "").map_err(VerificationError::PolicyViolation)?;
+    if estimate(password.expose(), "").strength < Strength::Fair {
+        return Err(VerificationError::WeakPassword);
+    }
+    Ok(())
+}
+
+/// The checks for a new master password that is entered twice.
+pub fn check_new_password(password1: &SecretString, password2: &SecretString) -> Result<(), VerificationError> {
+    check_password(password1)?;
+    check_strength(password1)?;
+    check_passwords(password1, password2)
+}
+
+fn check_passwords(password1: &SecretString, password2: &SecretString) -> Result<(), VerificationError> {
     if password1.ne(password2) {
         return Err(VerificationError::MismatchedPasswords);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn master_passwords_need_to_be_fair() {
+        assert_eq!(check_strength(&"qmhzwfk1".into()), Ok(()));
+        assert_eq!(check_strength(&"password1".into()), Err(VerificationError::WeakPassword));
+        assert_eq!(
+            check_strength(&"qmhzwfkt".into()),
+            Err(VerificationError::PolicyViolation(RuleViolation::MissingNumberOrSymbol))
+        );
+    }
+}
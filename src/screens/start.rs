@@ -1,19 +1,41 @@
+use std::path::Path;
 use std::thread::spawn;
 
+use druid::im::Vector;
 use druid::widget::{Controller, Flex, Label, Spinner};
 use druid::{Data, Env, Event, EventCtx, LifeCycle, LifeCycleCtx, Selector, Target, Widget, WidgetExt};
 
-use crate::data::{Database, Password};
+use crate::data::Database;
+use crate::screens::lock::LockState;
 use crate::screens::main::MainState;
+use crate::screens::popup::PopupState;
 use crate::screens::setup::SetupState;
 use crate::screens::{AppState, MainUi, Navigator};
 
 #[derive(Clone, Data)]
-pub struct StartupState {}
+pub struct StartupState {
+    path: Option<String>,
+    opened: Vector<Database>
+}
 
 impl StartupState {
     pub fn new() -> Self {
-        StartupState {}
+        StartupState {
+            path: None,
+            opened: Vector::new()
+        }
+    }
+
+    pub fn open(path: String) -> Self {
+        StartupState {
+            path: Some(path),
+            opened: Vector::new()
+        }
+    }
+
+    /// Opens another database while keeping the already opened ones.
+    pub fn add(path: String, opened: Vector<Database>) -> Self {
+        StartupState { path: Some(path), opened }
     }
 
     pub fn widget() -> impl Widget<Self> + 'static {
@@ -32,6 +54,32 @@ impl From<StartupState> for AppState {
     }
 }
 
+/// Where to go when a database could not be opened: back to the ones that are still open or to the setup screen.
+fn fallback(opened: Vector<Database>) -> AppState {
+    match opened.is_empty() {
+        true => SetupState::new().into(),
+        false => MainState::new(opened).into()
+    }
+}
+
+/// Removes a database that could not be found from the recently used ones, together with its saved password.
+pub fn forget_confirmed(ctx: &EventCtx, path: String) {
+    ctx.get_external_handle()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            ui.close_popup();
+            let result = ui
+                .settings
+                .forget_database(&path)
+                .and_then(|_| ui.settings.save());
+            if let Err(err) = result {
+                ui.open_popup(err.into());
+            }
+            if let AppState::Setup(state) = &mut ui.state {
+                state.reload_recent(&ui.settings);
+            }
+        })
+}
+
 struct LoadDatabase;
 
 const BRING_TO_FRONT: Selector = Selector::new("lam.focus");
@@ -51,33 +99,62 @@ impl<W: Widget<StartupState>> Controller<StartupState, W> for LoadDatabase {
     fn lifecycle(&mut self, child: &mut W, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &StartupState, env: &Env) {
         if let LifeCycle::WidgetAdded = event {
             let handle = ctx.get_external_handle();
-            handle
-                .clone()
-                .add_idle_callback(move |ui: &mut MainUi| match ui.settings.last_database.clone() {
+            let requested = data.path.clone();
+            let opened = data.opened.clone();
+            let startup = requested.is_none();
+            handle.clone().add_idle_callback(move |ui: &mut MainUi| {
+                match requested.or_else(|| ui.settings.last_database().map(|db| db.path.clone())) {
                     None => ui.open(SetupState::new()),
+                    // The file might only be missing for a while, e.g. on a drive that is not connected, so the
+                    // database and its saved password are only forgotten when the user asks for it.
+                    Some(path) if !Path::new(&path).exists() => {
+                        ui.open_popup(PopupState::missing_database(path));
+                        ui.open(fallback(opened));
+                    }
                     Some(path) => {
-                        let force_focus = ui.settings.force_focus;
-                        spawn(move || {
-                            println!("Start loading database");
-                            let database = Password::get(&path)
-                                .map_err(anyhow::Error::from)
-                                .and_then(|pw| Database::load(&path, &pw));
-                            match database {
-                                Ok(database) => handle.open(MainState::new(database)),
-                                Err(err) => {
-                                    handle.open_popup(err.into());
-                                    handle.open(SetupState::new());
-                                }
-                            }
-                            if force_focus {
-                                handle
-                                    .submit_command(BRING_TO_FRONT, (), Target::Auto)
-                                    .unwrap_or_else(|err| println!("Could not bring window to front: {}", err));
+                        if let Err(err) = ui
+                            .settings
+                            .open_database(&path)
+                            .and_then(|_| ui.settings.save())
+                        {
+                            ui.open_popup(err.into());
+                        }
+                        match ui.settings.password_store(&path) {
+                            None => ui.open(LockState::new(path, None).alongside(opened)),
+                            Some(store) => {
+                                let force_focus = startup && ui.settings.force_focus;
+                                spawn(move || {
+                                    println!("Start loading database");
+                                    match store.get().get(&path) {
+                                        Ok(Some(password)) => match Database::load(&path, &password) {
+                                            Ok(database) => {
+                                                let mut databases = opened;
+                                                databases.push_back(database);
+                                                handle.open(MainState::new(databases))
+                                            }
+                                            Err(err) => {
+                                                handle.open_popup(err.into());
+                                                handle.open(fallback(opened));
+                                            }
+                                        },
+                                        Ok(None) => handle.open(LockState::new(path, Some(store)).alongside(opened)),
+                                        Err(err) => {
+                                            handle.open_popup(err.into());
+                                            handle.open(LockState::new(path, Some(store)).alongside(opened));
+                                        }
+                                    }
+                                    if force_focus {
+                                        handle
+                                            .submit_command(BRING_TO_FRONT, (), Target::Auto)
+                                            .unwrap_or_else(|err| println!("Could not bring window to front: {}", err));
+                                    }
+                                    println!("Finished loading database");
+                                });
                             }
-                            println!("Finished loading database");
-                        });
+                        }
                     }
-                });
+                }
+            });
         }
         child.lifecycle(ctx, event, data, env)
     }
@@ -0,0 +1,158 @@
+use druid::theme::{BACKGROUND_LIGHT, BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
+use druid::widget::{Button, Container, Controller, Flex, Label, List, MainAxisAlignment};
+use druid::{lens, Data, Env, Event, EventCtx, Lens, LensExt, Selector, Widget, WidgetExt};
+use druid_material_icons::normal::action::DELETE_FOREVER;
+use druid_material_icons::normal::navigation::ARROW_BACK;
+
+use crate::data::{AccountId, TrashedAccount};
+use crate::screens::edit::EditState;
+use crate::screens::popup::PopupState;
+use crate::screens::{AppState, MainUi, Navigator};
+use crate::util::icon_text_button;
+use crate::util::theme::TEXT_SIZE_SMALL;
+use crate::widgets::{Icon, WidgetButton};
+
+const RESTORE_ACCOUNT: Selector<AccountId> = Selector::new("lol_account_manager_v2.trash.restore");
+
+#[derive(Clone, Data, Lens)]
+pub struct TrashState {
+    pub previous: EditState
+}
+
+impl TrashState {
+    pub fn new(previous: EditState) -> Self {
+        Self { previous }
+    }
+
+    /// Permanently deletes an account from the trash, or every account when `id` is `None`.
+    fn purge(&mut self, id: Option<AccountId>) {
+        self.previous.checkpoint();
+        let database = self.previous.database_mut();
+        match id {
+            Some(id) => database.purge(id),
+            None => database.trash.clear()
+        }
+    }
+
+    pub fn widget() -> impl Widget<Self> + 'static {
+        build_trash_ui()
+    }
+}
+
+impl From<TrashState> for AppState {
+    fn from(value: TrashState) -> Self {
+        AppState::Trash(value)
+    }
+}
+
+/// Called by the confirmation popup.
+pub fn purge_confirmed(ctx: &EventCtx, id: Option<AccountId>) {
+    ctx.get_external_handle()
+        .add_idle_callback(move |ui: &mut MainUi| {
+            ui.close_popup();
+            if let AppState::Trash(state) = &mut ui.state {
+                state.purge(id);
+            }
+        })
+}
+
+fn build_trash_ui() -> impl Widget<TrashState> {
+    Flex::column()
+        .with_child(
+            Label::dynamic(|state: &TrashState, _| format!("Trash of {}", state.previous.database().name()))
+                .center()
+                .padding(3.0)
+        )
+        .with_spacer(3.0)
+        .with_flex_child(
+            List::new(item_ui)
+                .with_spacing(3.0)
+                .scroll()
+                .vertical()
+                .lens(lens::Identity.map(
+                    |state: &TrashState| state.previous.database().trash.clone(),
+                    |state: &mut TrashState, trash| state.previous.database_mut().trash = trash
+                ))
+                .controller(Restorer)
+                .expand()
+                .padding(3.0)
+                .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+                .rounded(TEXTBOX_BORDER_RADIUS),
+            1.0
+        )
+        .with_spacer(3.0)
+        .with_child(
+            Flex::row()
+                .main_axis_alignment(MainAxisAlignment::SpaceEvenly)
+                .with_flex_child(
+                    icon_text_button(DELETE_FOREVER, "Empty Trash")
+                        .on_click(|ctx, _, _| ctx.open_popup(PopupState::Purge(None)))
+                        .disabled_if(|state: &TrashState, _| state.previous.database().trash.is_empty())
+                        .expand(),
+                    1.0
+                )
+                .with_spacer(3.0)
+                .with_flex_child(
+                    icon_text_button(ARROW_BACK, "Back")
+                        .on_click(|ctx, _, _| ctx.back())
+                        .expand(),
+                    1.0
+                )
+                .expand_width()
+                .fix_height(50.0)
+        )
+        .padding(5.0)
+}
+
+fn item_ui() -> impl Widget<TrashedAccount> {
+    Container::new(
+        Flex::row()
+            .with_flex_child(
+                Flex::column()
+                    .with_child(Label::dynamic(|entry: &TrashedAccount, _| entry.account.name.clone()))
+                    .with_child(
+                        Label::dynamic(|entry: &TrashedAccount, _| match entry.days_in_trash() {
+                            0 => "Deleted today".to_string(),
+                            1 => "Deleted yesterday".to_string(),
+                            days => format!("Deleted {} days ago", days)
+                        })
+                        .with_text_size(TEXT_SIZE_SMALL)
+                    )
+                    .center()
+                    .expand(),
+                1.0
+            )
+            .with_spacer(3.0)
+            .with_child(
+                Button::new("Restore")
+                    .on_click(|ctx, entry: &mut TrashedAccount, _| ctx.submit_command(RESTORE_ACCOUNT.with(entry.account.id)))
+                    .expand_height()
+            )
+            .with_spacer(3.0)
+            .with_child(
+                WidgetButton::new(Icon::new(DELETE_FOREVER).expand_height().padding(3.0))
+                    .on_click(|ctx, entry: &mut TrashedAccount, _| ctx.open_popup(PopupState::Purge(Some(entry.account.id))))
+            )
+    )
+    .expand_width()
+    .padding(8.0)
+    .fix_height(60.0)
+    .background(BACKGROUND_LIGHT)
+    .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
+    .rounded(TEXTBOX_BORDER_RADIUS)
+}
+
+struct Restorer;
+
+impl<W: Widget<TrashState>> Controller<TrashState, W> for Restorer {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut TrashState, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some(id) = cmd.get(RESTORE_ACCOUNT).cloned() {
+                data.previous.checkpoint();
+                data.previous.database_mut().restore(id);
+                ctx.set_handled();
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
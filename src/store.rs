@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use druid::Data;
+use keyring::Entry;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::util::SecretString;
+
+pub trait SecretStore: Send + Sync {
+    fn get(&self, key: &str) -> anyhow::Result<Option<SecretString>>;
+    fn store(&self, key: &str, secret: &SecretString) -> anyhow::Result<()>;
+    fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Data, Serialize, Deserialize)]
+pub enum SecretStoreKind {
+    #[default]
+    Keyring,
+    Memory,
+    Disabled
+}
+
+impl SecretStoreKind {
+    pub fn get(self) -> &'static dyn SecretStore {
+        static MEMORY: Lazy<MemoryStore> = Lazy::new(MemoryStore::default);
+        match self {
+            SecretStoreKind::Keyring => &KeyringStore,
+            SecretStoreKind::Memory => &*MEMORY,
+            SecretStoreKind::Disabled => &NoStore
+        }
+    }
+}
+
+pub struct KeyringStore;
+
+impl KeyringStore {
+    fn entry(key: &str) -> anyhow::Result<Entry> {
+        Entry::new(key, "local").map_err(keyring_error)
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, key: &str) -> anyhow::Result<Option<SecretString>> {
+        match Self::entry(key)?.get_password() {
+            Ok(secret) => Ok(Some(secret.into())),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(keyring_error(err))
+        }
+    }
+
+    fn store(&self, key: &str, secret: &SecretString) -> anyhow::Result<()> {
+        Self::entry(key)?
+            .set_password(secret.expose())
+            .map_err(keyring_error)
+    }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match Self::entry(key)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(keyring_error(err))
+        }
+    }
+}
+
+fn keyring_error(err: keyring::Error) -> anyhow::Error {
+    match err {
+        keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_) => anyhow::Error::from(err)
+            .context("The system keyring is not available. Unlock it or choose a different password storage in the settings."),
+        err => anyhow!("Failed to access the system keyring: {}", err)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStore(Mutex<HashMap<String, SecretString>>);
+
+impl SecretStore for MemoryStore {
+    fn get(&self, key: &str) -> anyhow::Result<Option<SecretString>> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn store(&self, key: &str, secret: &SecretString) -> anyhow::Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), secret.clone());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.0.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+pub struct NoStore;
+
+impl SecretStore for NoStore {
+    fn get(&self, _key: &str) -> anyhow::Result<Option<SecretString>> {
+        Ok(None)
+    }
+
+    fn store(&self, _key: &str, _secret: &SecretString) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn delete(&self, _key: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_store_round_trip() {
+        let store = MemoryStore::default();
+        assert_eq!(store.get("a.age").unwrap(), None);
+        store.store("a.age", &"first".into()).unwrap();
+        store.store("a.age", &"second".into()).unwrap();
+        store.store("b.age", &"other".into()).unwrap();
+        assert_eq!(store.get("a.age").unwrap(), Some("second".into()));
+        store.delete("a.age").unwrap();
+        store.delete("a.age").unwrap();
+        assert_eq!(store.get("a.age").unwrap(), None);
+        assert_eq!(store.get("b.age").unwrap(), Some("other".into()));
+    }
+
+    #[test]
+    fn disabled_store_keeps_nothing() {
+        let store = SecretStoreKind::Disabled.get();
+        store.store("a.age", &"secret".into()).unwrap();
+        assert_eq!(store.get("a.age").unwrap(), None);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::{Display, Formatter};
+
+use druid::Data;
+
+use crate::util::SecretString;
+
+/// Passwords that show up at the top of every leaked password list, together with words that are popular for League accounts.
+const COMMON: &[&str] = &[
+    "password", "passwort", "123456", "qwerty", "azerty", "letmein", "welcome", "iloveyou", "admin", "monkey", "dragon", "football", "baseball",
+    "master", "shadow", "sunshine", "princess", "trustno1", "league", "legends", "riot", "summoner", "teemo", "yasuo"
+];
+/// Characters that are next to each other in one of these rows form a sequence.
+const ROWS: &[&str] = &[
+    "abcdefghijklmnopqrstuvwxyz",
+    "0123456789",
+    "qwertyuiop",
+    "asdfghjkl",
+    "zxcvbnm",
+    "qwertzuiop",
+    "yxcvbnm"
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Data)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong
+}
+
+impl Display for Strength {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strength::VeryWeak => f.write_str("Very weak"),
+            Strength::Weak => f.write_str("Weak"),
+            Strength::Fair => f.write_str("Fair"),
+            Strength::Strong => f.write_str("Strong"),
+            Strength::VeryStrong => f.write_str("Very strong")
+        }
+    }
+}
+
+/// The most important reason why a password is easier to guess than its length suggests.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Data)]
+pub enum Weakness {
+    Username,
+    Common,
+    Sequence,
+    Short
+}
+
+impl Display for Weakness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Weakness::Username => f.write_str("contains the username"),
+            Weakness::Common => f.write_str("contains a common password"),
+            Weakness::Sequence => f.write_str("contains a sequence like abc or 111"),
+            Weakness::Short => f.write_str("too short")
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Data)]
+pub struct Estimate {
+    /// The estimated entropy in bits.
+    pub bits: f64,
+    pub strength: Strength,
+    pub weakness: Option<Weakness>
+}
+
+impl Estimate {
+    /// The entropy scaled to `0.0..=1.0` for a progress bar.
+    pub fn score(&self) -> f64 {
+        (self.bits / 100.0).clamp(0.0, 1.0)
+    }
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.weakness {
+            Some(weakness) => write!(f, "{}, {}", self.strength, weakness),
+            None => write!(f, "{}", self.strength)
+        }
+    }
+}
+
+/// Rates `password`, or returns `None` if it is empty, which is reported separately everywhere.
+pub fn rate(password: &SecretString, username: &str) -> Option<Estimate> {
+    match password.is_empty() {
+        true => None,
+        false => Some(estimate(password.expose(), username))
+    }
+}
+
+/// Estimates how hard `password` is to guess. The entropy of random characters is reduced for parts that an attacker
+/// would try early: the username, common passwords, repeated characters and sequences.
+pub fn estimate(password: &str, username: &str) -> Estimate {
+    let chars: Vec<char> = password.to_lowercase().chars().collect();
+    let per_char = (pool_size(password) as f64).log2();
+    // Characters that belong to a pattern cost an attacker about one bit each.
+    let mut covered = vec![false; chars.len()];
+    let mut weakness = None;
+
+    let username: Vec<char> = username.trim().to_lowercase().chars().collect();
+    if username.len() >= 3 && cover(&chars, &username, &mut covered) {
+        weakness = Some(Weakness::Username);
+    }
+    for common in COMMON {
+        let common: Vec<char> = common.chars().collect();
+        if cover(&chars, &common, &mut covered) {
+            weakness = weakness.or(Some(Weakness::Common));
+        }
+    }
+    let mut run = 1;
+    for index in 1..chars.len() {
+        run = match follows(chars[index - 1], chars[index]) {
+            true => run + 1,
+            false => 1
+        };
+        if run >= 3 {
+            covered[index + 1 - run..=index].fill(true);
+            weakness = weakness.or(Some(Weakness::Sequence));
+        }
+    }
+
+    let bits: f64 = covered
+        .iter()
+        .map(|covered| if *covered { 1.0 } else { per_char })
+        .sum();
+    if chars.len() < 8 {
+        weakness = weakness.or(Some(Weakness::Short));
+    }
+    let strength = match bits {
+        bits if bits < 28.0 => Strength::VeryWeak,
+        bits if bits < 36.0 => Strength::Weak,
+        bits if bits < 60.0 => Strength::Fair,
+        bits if bits < 80.0 => Strength::Strong,
+        _ => Strength::VeryStrong
+    };
+    Estimate { bits, strength, weakness }
+}
+
+/// The amount of characters an attacker has to try for every position, based on the classes that appear in `password`.
+fn pool_size(password: &str) -> u32 {
+    let classes = [
+        (password.chars().any(|c| c.is_ascii_lowercase()), 26),
+        (password.chars().any(|c| c.is_ascii_uppercase()), 26),
+        (password.chars().any(|c| c.is_ascii_digit()), 10),
+        (
+            password
+                .chars()
+                .any(|c| c.is_ascii_punctuation() || c == ' '),
+            33
+        ),
+        (password.chars().any(|c| !c.is_ascii()), 100)
+    ];
+    classes
+        .iter()
+        .filter(|(present, _)| *present)
+        .map(|(_, size)| size)
+        .sum::<u32>()
+        .max(1)
+}
+
+/// Marks every occurrence of `pattern` in `chars` and returns whether there was one.
+fn cover(chars: &[char], pattern: &[char], covered: &mut [bool]) -> bool {
+    let mut found = false;
+    for start in 0..chars.len().saturating_sub(pattern.len() - 1) {
+        if chars[start..].starts_with(pattern) {
+            covered[start..start + pattern.len()].fill(true);
+            found = true;
+        }
+    }
+    found
+}
+
+/// Whether `next` repeats `previous` or continues a sequence with it in either direction.
+fn follows(previous: char, next: char) -> bool {
+    previous == next
+        || ROWS
+            .iter()
+            .any(|row| match (row.find(previous), row.find(next)) {
+                (Some(a), Some(b)) => a.abs_diff(b) == 1,
+                _ => false
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_cover_every_character_of_the_run() {
+        let estimate = estimate("abc", "");
+        assert_eq!(estimate.bits, 3.0);
+        assert_eq!(estimate.weakness, Some(Weakness::Sequence));
+        assert_eq!(super::estimate("abcd", "").bits, 4.0);
+        assert_eq!(super::estimate("aaa", "").bits, 3.0);
+    }
+
+    #[test]
+    fn two_characters_are_no_sequence() {
+        let estimate = estimate("ab", "");
+        assert_eq!(estimate.bits, 2.0 * 26f64.log2());
+        assert_eq!(estimate.weakness, Some(Weakness::Short));
+    }
+
+    #[test]
+    fn common_passwords() {
+        let estimate = estimate("password", "");
+        assert_eq!(estimate.bits, 8.0);
+        assert_eq!(estimate.strength, Strength::VeryWeak);
+        assert_eq!(estimate.weakness, Some(Weakness::Common));
+    }
+
+    #[test]
+    fn username_containment() {
+        assert_eq!(estimate("myfaker2024!", "FAKER").weakness, Some(Weakness::Username));
+        // Very short usernames would match by chance.
+        assert_eq!(estimate("K7#zq!vP", "zq").weakness, None);
+    }
+
+    #[test]
+    fn strength_thresholds() {
+        // Lowercase letters without any pattern are worth log2(26) bits each.
+        assert_eq!(estimate("qmhzwfk", "").strength, Strength::Weak);
+        assert_eq!(estimate("qmhzwfkt", "").strength, Strength::Fair);
+        assert_eq!(estimate("qmhzwfkt", "").weakness, None);
+        assert_eq!(estimate("Qm7#hzWf!k2$tR9&vX4@", "").strength, Strength::VeryStrong);
+    }
+
+    #[test]
+    fn empty_passwords_are_not_rated() {
+        assert_eq!(rate(&SecretString::default(), ""), None);
+    }
+}
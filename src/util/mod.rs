@@ -1,4 +1,5 @@
 mod indexed;
+mod secret;
 pub mod string_list;
 pub mod theme;
 
@@ -6,12 +7,15 @@ use std::ops::Not;
 
 use druid::commands::{OPEN_FILE, SAVE_FILE_AS, SHOW_OPEN_PANEL, SHOW_SAVE_PANEL};
 use druid::text::{EditableText, TextStorage};
-use druid::theme::{BORDER_DARK, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
-use druid::widget::{Button, Controller, CrossAxisAlignment, Either, Flex, Label, Scope, TextBox};
-use druid::{Command, Data, Env, Event, EventCtx, FileDialogOptions, Lens, Widget, WidgetExt};
+use druid::theme::{BORDER_DARK, PRIMARY_DARK, PRIMARY_LIGHT, TEXTBOX_BORDER_RADIUS, TEXTBOX_BORDER_WIDTH};
+use druid::widget::{Button, Controller, CrossAxisAlignment, Either, Flex, Label, Maybe, ProgressBar, Scope, TextBox};
+use druid::{lens, Command, Data, Env, Event, EventCtx, FileDialogOptions, Lens, LensExt, Widget, WidgetExt};
 use druid_material_icons::IconPaths;
 pub use indexed::{IndexWrapper, Indexed};
+pub use secret::SecretString;
 
+use crate::strength::{Estimate, Strength};
+use crate::util::theme::{RED, TEXT_SIZE_SMALL};
 use crate::widgets::{Icon, WidgetButton};
 
 pub fn icon_text_button<T: Data>(icon: IconPaths, text: &str) -> impl Widget<T> {
@@ -52,7 +56,31 @@ pub fn ternary<T>(a: bool, v1: T, v2: T) -> T {
     if a { v1 } else { v2 }
 }
 
-pub fn password_field<T: EditableText + TextStorage>(name: &str) -> impl Widget<T> {
+pub fn password_field(name: &str) -> impl Widget<SecretString> {
+    password_input(name, None)
+}
+
+/// A password field with an additional "Generate" button. `generate` is called when it is clicked and is expected to
+/// open the password generator.
+pub fn generated_password_field(name: &str, generate: impl Fn(&EventCtx) + 'static) -> impl Widget<SecretString> {
+    password_input(name, Some(Box::new(generate)))
+}
+
+fn password_input(name: &str, generate: Option<Box<dyn Fn(&EventCtx)>>) -> impl Widget<SecretString> {
+    let mut buttons = Flex::row().with_child(
+        Button::dynamic(|state: &bool, _| ternary(*state, "Hide", "Show").to_string())
+            .on_click(|_, state: &mut bool, _| *state = state.not())
+            .fix_width(62.0)
+            .lens(PasswordState::visible)
+    );
+    if let Some(generate) = generate {
+        buttons.add_spacer(3.0);
+        buttons.add_child(
+            Button::new("Generate")
+                .on_click(move |ctx, _, _| generate(ctx))
+                .fix_width(80.0)
+        );
+    }
     Flex::column()
         .cross_axis_alignment(CrossAxisAlignment::Start)
         .with_child(Label::new(name))
@@ -64,7 +92,7 @@ pub fn password_field<T: EditableText + TextStorage>(name: &str) -> impl Widget<
                 Flex::row()
                     .with_flex_child(
                         Either::new(
-                            |state: &PasswordState<_>, _| state.visible,
+                            |state: &PasswordState<String>, _| state.visible,
                             TextBox::new().lens(PasswordState::password),
                             TextBox::protected()
                                 .fix_height(34.0)
@@ -74,12 +102,7 @@ pub fn password_field<T: EditableText + TextStorage>(name: &str) -> impl Widget<
                         1.0
                     )
                     .with_spacer(3.0)
-                    .with_child(
-                        Button::dynamic(|state: &bool, _| ternary(*state, "Hide", "Show").to_string())
-                            .on_click(|_, state: &mut bool, _| *state = state.not())
-                            .fix_width(62.0)
-                            .lens(PasswordState::visible)
-                    )
+                    .with_child(buttons)
             ) //TextBox::protected()
               //.expand_width()
         )
@@ -87,6 +110,30 @@ pub fn password_field<T: EditableText + TextStorage>(name: &str) -> impl Widget<
         .expand_width()
         .border(BORDER_DARK, TEXTBOX_BORDER_WIDTH)
         .rounded(TEXTBOX_BORDER_RADIUS)
+        .lens(SecretString::text())
+}
+
+/// A bar and a short verdict that show how hard a password is to guess. Nothing is shown for empty passwords.
+pub fn strength_meter() -> impl Widget<Option<Estimate>> {
+    Maybe::or_empty(|| {
+        Flex::row()
+            .with_flex_child(
+                ProgressBar::new()
+                    .lens(lens::Identity.map(Estimate::score, |_: &mut Estimate, _| {}))
+                    .expand_width(),
+                1.0
+            )
+            .with_spacer(6.0)
+            .with_child(Label::dynamic(|estimate: &Estimate, _| estimate.to_string()).with_text_size(TEXT_SIZE_SMALL))
+            .env_scope(|env, estimate: &Estimate| {
+                if estimate.strength < Strength::Fair {
+                    let red = env.get(RED);
+                    env.set(PRIMARY_LIGHT, red.clone());
+                    env.set(PRIMARY_DARK, red);
+                }
+            })
+            .padding((3.0, 0.0))
+    })
 }
 
 pub fn multiline_field<T: EditableText + TextStorage>(name: &str) -> impl Widget<T> {
@@ -0,0 +1,48 @@
+use std::fmt::{Debug, Formatter};
+
+use druid::{lens, Data, Lens};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// A string that gets wiped from memory when it is dropped and never shows up in debug output.
+#[derive(Clone, Default, Eq, PartialEq, Data, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn text() -> impl Lens<Self, String> {
+        lens!(SecretString, 0)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
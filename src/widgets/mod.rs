@@ -1,7 +1,9 @@
 mod button;
 pub mod icons;
+mod reorder;
 //mod protected_textbox;
 
 pub use button::WidgetButton;
 pub use icons::Icon;
+pub use reorder::{DragHandle, Reorder};
 //pub use protected_textbox::ProtectedTextBox;
@@ -0,0 +1,179 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use druid::widget::prelude::*;
+use druid::{theme, Cursor, Point, Rect, Selector, TimerToken};
+
+const BEGIN_DRAG: Selector = Selector::new("lol_account_manager_v2.reorder.begin");
+const SCROLL_INTERVAL: Duration = Duration::from_millis(30);
+const SCROLL_MARGIN: f64 = 40.0;
+
+/// The part of a row that can be grabbed to drag it. Has to be placed inside a [`Reorder`].
+pub struct DragHandle<T, W> {
+    inner: W,
+    _phantom: PhantomData<T>
+}
+
+impl<T: Data, W: Widget<T>> DragHandle<T, W> {
+    pub fn new(inner: W) -> Self {
+        DragHandle {
+            inner,
+            _phantom: Default::default()
+        }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for DragHandle<T, W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, _env: &Env) {
+        match event {
+            Event::MouseMove(_) => ctx.set_cursor(&Cursor::ResizeUpDown),
+            Event::MouseDown(_) if !ctx.is_disabled() => {
+                ctx.set_active(true);
+                ctx.submit_notification(BEGIN_DRAG);
+                ctx.set_handled();
+            }
+            Event::MouseUp(_) => ctx.set_active(false),
+            _ => ()
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.inner.paint(ctx, data, env)
+    }
+}
+
+struct Drag {
+    from: usize,
+    gap: usize,
+    window_pos: Point
+}
+
+/// Lets the rows of a list with a fixed row height be rearranged by dragging their [`DragHandle`].
+///
+/// Has to be placed inside the `Scroll` of the list, so that it can scroll when the pointer gets close to the edges.
+pub struct Reorder<T, W> {
+    inner: W,
+    row_height: f64,
+    spacing: f64,
+    on_move: Box<dyn Fn(&mut EventCtx, &mut T, usize, usize)>,
+    press: Point,
+    drag: Option<Drag>,
+    timer: TimerToken
+}
+
+impl<T: Data, W: Widget<T>> Reorder<T, W> {
+    /// `on_move` receives the current index of the dragged row and the index it should end up at.
+    pub fn new(inner: W, row_height: f64, spacing: f64, on_move: impl Fn(&mut EventCtx, &mut T, usize, usize) + 'static) -> Self {
+        Self {
+            inner,
+            row_height,
+            spacing,
+            on_move: Box::new(on_move),
+            press: Point::ZERO,
+            drag: None,
+            timer: TimerToken::INVALID
+        }
+    }
+
+    fn pitch(&self) -> f64 {
+        self.row_height + self.spacing
+    }
+
+    fn rows(&self, size: Size) -> usize {
+        ((size.height + self.spacing) / self.pitch()).round() as usize
+    }
+
+    fn update_gap(&mut self, ctx: &mut EventCtx) {
+        let rows = self.rows(ctx.size());
+        let pitch = self.pitch();
+        let origin = ctx.window_origin().to_vec2();
+        if let Some(drag) = &mut self.drag {
+            let y = (drag.window_pos - origin).y;
+            drag.gap = ((y / pitch).round().max(0.0) as usize).min(rows);
+            ctx.request_paint();
+        }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for Reorder<T, W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) => self.press = mouse.pos,
+            Event::Notification(notification) if notification.is(BEGIN_DRAG) => {
+                let from = (self.press.y / self.pitch()).floor().max(0.0) as usize;
+                self.drag = Some(Drag {
+                    from,
+                    gap: from,
+                    window_pos: self.press + ctx.window_origin().to_vec2()
+                });
+                self.timer = ctx.request_timer(SCROLL_INTERVAL);
+                ctx.set_handled();
+            }
+            Event::MouseMove(mouse) if self.drag.is_some() => {
+                if let Some(drag) = &mut self.drag {
+                    drag.window_pos = mouse.window_pos;
+                }
+                self.update_gap(ctx);
+            }
+            Event::Timer(token) if *token == self.timer && self.drag.is_some() => {
+                // Keeps scrolling for as long as the pointer stays close to an edge, even if it is not moving.
+                if let Some(drag) = &self.drag {
+                    let y = (drag.window_pos - ctx.window_origin().to_vec2()).y;
+                    ctx.scroll_area_to_view(Rect::new(0.0, y - SCROLL_MARGIN, ctx.size().width, y + SCROLL_MARGIN));
+                }
+                self.update_gap(ctx);
+                self.timer = ctx.request_timer(SCROLL_INTERVAL);
+            }
+            Event::MouseUp(_) => {
+                if let Some(drag) = self.drag.take() {
+                    let to = match drag.gap > drag.from {
+                        true => drag.gap - 1,
+                        false => drag.gap
+                    };
+                    if to != drag.from {
+                        (self.on_move)(ctx, data, drag.from, to);
+                    }
+                    ctx.request_paint();
+                }
+            }
+            _ => {}
+        }
+        self.inner.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.inner.paint(ctx, data, env);
+        if let Some(drag) = &self.drag {
+            let size = ctx.size();
+            let y = (drag.gap as f64 * self.pitch() - self.spacing / 2.0)
+                .min(size.height - 1.0)
+                .max(1.0);
+            let line = Rect::new(0.0, y - 1.0, size.width, y + 1.0);
+            ctx.fill(line, &env.get(theme::PRIMARY_LIGHT));
+        }
+    }
+}